@@ -1,59 +1,479 @@
 use bevy::{
-    app::AppExit, asset::AssetLoader, audio::AudioLoader, prelude::*, window::PrimaryWindow,
+    app::AppExit, asset::AssetLoader, audio::AudioLoader, input::InputSystem, prelude::*,
+    window::PrimaryWindow,
 };
 use bevy_xp::{
-    events::GameOver,
-    resources::{EnemySpawnTimer, HighScores, Score, StarSpawnTimer},
+    achievements::AchievementCatalog,
+    assets::{AssetManifest, RonAssetLoader},
+    enemies::EnemyCatalog,
+    events::{
+        AchievementUnlocked, BossDefeated, BossSpawned, CameraShake, EnemyBounced, GameOver,
+        NearMiss, PlaySfx, PlayerDamaged, ScoreGained, WaveReleased,
+    },
+    game_modes::GameMode,
+    input::Action,
+    levels::Level,
+    resources::{
+        BossEncounters, EditorMode, EnemySpawnTimer, EnemyTimeScale, EntityBudget, GameAssets,
+        HighScores, LevelEditor, LevelProgress, LevelSelection, LoadingProgress, LocalPlayers,
+        MusicIntensity, PlayerGamepads, PopulationCaps, PowerUpSpawnTimer, QuitConfirmation, Score,
+        ShooterMode, StarSpawnTimer,
+    },
+    states::{AppState, SimulationState},
     systems::{
-        collisions::{enemy_hit_player, player_hit_star},
-        exit_game, handle_game_over,
+        achievements::{
+            load_achievement_progress, reset_run_achievements, save_achievement_progress,
+            spawn_achievement_toasts, tick_achievement_toasts, track_achievements,
+        },
+        audio::{
+            crossfade_music, load_audio_manager, play_sfx, play_state_music, save_audio_manager,
+            set_spatial_audio_scale, update_music_intensity, update_sfx_volume,
+        },
+        bosses::{
+            boss_attacks, damage_bosses, defeat_bosses, despawn_boss_health_bar,
+            reset_boss_encounters, spawn_boss_health_bar, spawn_bosses, update_boss_health_bar,
+        },
+        camera::{
+            add_camera_trauma, follow_players, load_camera_settings, save_camera_settings,
+            spawn_camera, update_camera_transform,
+        },
+        collisions::{enemy_hit_player, player_hit_star, player_projectile_hit_enemy},
+        editor::{
+            close_level_editor, despawn_level_editor_hud, draw_level_editor, level_editor_exit,
+            level_editor_file_input, level_editor_keyboard_input, level_editor_mouse_input,
+            open_level_editor, spawn_level_editor_hud, update_level_editor_hud,
+        },
+        game_modes::{
+            apply_game_mode_rules, despawn_game_mode_hud, spawn_game_mode_hud, update_game_mode_hud,
+        },
+        handle_game_over,
+        input::{
+            handle_gamepad_connections, load_input_bindings, save_input_bindings,
+            update_action_input, update_aim, update_movement_axis,
+        },
+        levels::{
+            check_level_completion, release_waves, reset_level_progress, spawn_level,
+            tick_level_clock,
+        },
+        lives::{blink_invulnerable_players, handle_player_damage},
+        loading::{
+            check_game_assets, despawn_loading_screen, load_achievement_catalog,
+            load_asset_manifest, load_enemy_catalog, load_game_assets, spawn_loading_screen,
+            update_loading_screen,
+        },
+        menus::{
+            achievements_screen_input, despawn_achievements_screen, despawn_game_over_screen,
+            despawn_main_menu, despawn_stats_screen, game_over_input, main_menu_input,
+            spawn_achievements_screen, spawn_game_over_screen, spawn_main_menu, spawn_stats_screen,
+            stats_screen_input, update_main_menu_text,
+        },
         movements::{
-            confine_player_movement, enemy_movement, player_movement, update_enemy_direction,
+            confine_player_movement, enemy_movement, player_movement, player_projectile_movement,
+            projectile_movement, steer_enemies, update_enemy_direction,
+        },
+        near_misses::{animate_near_miss_popups, despawn_near_miss_popups, reward_near_misses},
+        obstacles::{
+            bounce_enemies_off_obstacles, despawn_obstacles, obstacles_block_projectiles,
+            slide_players_along_obstacles,
+        },
+        pause::{
+            despawn_pause_menu, pause_menu_input, pause_on_focus_lost, pause_simulation,
+            resume_simulation, spawn_pause_menu, toggle_simulation, update_pause_menu_text,
+        },
+        population::{enforce_population_caps, stamp_spawn_time, update_entity_budget},
+        power_ups::{
+            attract_stars, despawn_power_up_hud, despawn_power_ups, player_hit_power_up,
+            reset_enemy_time_scale, spawn_power_up_hud, spawn_power_ups_over_time,
+            tick_power_up_spawn_timer, tick_power_ups, update_enemy_time_scale,
+            update_power_up_hud,
+        },
+        scores::{
+            apply_score_gains, high_scores_updated, reset_score, tick_combos, update_high_scores,
+            update_score,
         },
-        scores::{high_scores_updated, update_high_scores, update_score},
         spawns::{
-            spawn_camera, spawn_enemies, spawn_enemies_over_time, spawn_player, spawn_stars,
-            spawn_stars_over_time, tick_enemy_spawn_timer, tick_star_spawn_timer,
+            despawn_enemies, despawn_player_projectiles, despawn_players, despawn_projectiles,
+            despawn_stars, expire_stars, fire_player_projectiles, fire_sniper_projectiles,
+            reset_spawn_timers, spawn_enemies_over_time, spawn_player, spawn_stars_over_time,
+            split_enemies, tick_enemy_spawn_timer, tick_star_spawn_timer,
         },
+        stats::{end_run_stats, load_stats, reset_run_stats, track_stats},
     },
     Result,
 };
 use rand::{random, Rng, RngCore};
 
 /// add stars, respawns time to time, update scores
+///
+/// run with `--coop` for a local two-player game, `--shooter` to shoot enemies down
+/// and `--editor` to edit the level layouts
 fn main() -> Result<()> {
+    let shooter_mode = ShooterMode {
+        enabled: std::env::args().any(|arg| arg == "--shooter"),
+    };
+    let editor_mode = EditorMode {
+        enabled: std::env::args().any(|arg| arg == "--editor"),
+    };
+    let local_players = if std::env::args().any(|arg| arg == "--coop") {
+        LocalPlayers::coop()
+    } else {
+        LocalPlayers::default()
+    };
+
     App::new()
         // plugins (start the game)
         .add_plugins(DefaultPlugins)
+        // assets
+        .init_asset::<AssetManifest>()
+        .init_asset_loader::<RonAssetLoader<AssetManifest>>()
+        .init_asset::<EnemyCatalog>()
+        .init_asset_loader::<RonAssetLoader<EnemyCatalog>>()
+        .init_asset::<AchievementCatalog>()
+        .init_asset_loader::<RonAssetLoader<AchievementCatalog>>()
+        .init_asset::<Level>()
+        .init_asset_loader::<RonAssetLoader<Level>>()
         // resources
         .init_resource::<Score>()
         .init_resource::<StarSpawnTimer>()
         .init_resource::<EnemySpawnTimer>()
         .init_resource::<HighScores>()
+        .init_resource::<QuitConfirmation>()
+        .init_resource::<Input<Action>>()
+        .init_resource::<PlayerGamepads>()
+        .init_resource::<LoadingProgress>()
+        .init_resource::<MusicIntensity>()
+        .init_resource::<BossEncounters>()
+        .init_resource::<PowerUpSpawnTimer>()
+        .init_resource::<EnemyTimeScale>()
+        .init_resource::<PopulationCaps>()
+        .init_resource::<EntityBudget>()
+        .init_resource::<LevelSelection>()
+        .init_resource::<LevelProgress>()
+        .init_resource::<GameMode>()
+        .insert_resource(local_players)
+        .insert_resource(shooter_mode)
+        .insert_resource(editor_mode)
+        // states
+        .add_state::<AppState>()
+        .add_state::<SimulationState>()
         // events
         .add_event::<GameOver>()
+        .add_event::<PlaySfx>()
+        .add_event::<PlayerDamaged>()
+        .add_event::<ScoreGained>()
+        .add_event::<EnemyBounced>()
+        .add_event::<BossSpawned>()
+        .add_event::<BossDefeated>()
+        .add_event::<WaveReleased>()
+        .add_event::<AchievementUnlocked>()
+        .add_event::<NearMiss>()
+        .add_event::<CameraShake>()
+        // input
+        .add_systems(Startup, load_input_bindings)
+        .add_systems(
+            PreUpdate,
+            (
+                handle_gamepad_connections,
+                update_action_input,
+                update_movement_axis,
+                update_aim,
+            )
+                .chain()
+                .after(InputSystem),
+        )
+        .add_systems(Update, save_input_bindings)
+        // audio
+        .add_systems(Startup, (load_audio_manager, set_spatial_audio_scale))
+        .add_systems(
+            Update,
+            (
+                play_sfx.run_if(resource_exists::<GameAssets>()),
+                play_state_music.run_if(resource_exists::<GameAssets>()),
+                update_music_intensity,
+                crossfade_music,
+                update_sfx_volume,
+                save_audio_manager,
+            ),
+        )
+        // loading
+        .add_systems(
+            Startup,
+            (
+                load_asset_manifest,
+                load_enemy_catalog,
+                load_achievement_catalog,
+            ),
+        )
+        .add_systems(OnEnter(AppState::Loading), spawn_loading_screen)
+        .add_systems(
+            Update,
+            (
+                load_game_assets.run_if(not(resource_exists::<GameAssets>())),
+                check_game_assets.run_if(resource_exists::<GameAssets>()),
+                update_loading_screen,
+            )
+                .run_if(in_state(AppState::Loading)),
+        )
+        .add_systems(OnExit(AppState::Loading), despawn_loading_screen)
+        // menus
+        .add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+        .add_systems(
+            Update,
+            (main_menu_input, update_main_menu_text).run_if(in_state(AppState::MainMenu)),
+        )
+        .add_systems(OnExit(AppState::MainMenu), despawn_main_menu)
+        .add_systems(OnEnter(AppState::GameOver), spawn_game_over_screen)
+        .add_systems(Update, game_over_input.run_if(in_state(AppState::GameOver)))
+        .add_systems(OnExit(AppState::GameOver), despawn_game_over_screen)
+        .add_systems(OnEnter(AppState::Achievements), spawn_achievements_screen)
+        .add_systems(
+            Update,
+            achievements_screen_input.run_if(in_state(AppState::Achievements)),
+        )
+        .add_systems(OnExit(AppState::Achievements), despawn_achievements_screen)
+        .add_systems(OnEnter(AppState::Stats), spawn_stats_screen)
+        .add_systems(Update, stats_screen_input.run_if(in_state(AppState::Stats)))
+        .add_systems(OnExit(AppState::Stats), despawn_stats_screen)
+        // achievements
+        .add_systems(Startup, load_achievement_progress)
+        .add_systems(OnEnter(AppState::Playing), reset_run_achievements)
+        .add_systems(
+            Update,
+            (
+                track_achievements,
+                save_achievement_progress
+                    .run_if(on_event::<GameOver>().or_else(on_event::<AchievementUnlocked>())),
+                spawn_achievement_toasts,
+            )
+                .chain()
+                .run_if(resource_exists::<AchievementCatalog>()),
+        )
+        .add_systems(Update, tick_achievement_toasts)
+        // stats
+        .add_systems(Startup, load_stats)
+        .add_systems(OnEnter(AppState::Playing), reset_run_stats)
+        .add_systems(
+            Update,
+            track_stats
+                .after(apply_score_gains)
+                .after(tick_level_clock)
+                .before(handle_player_damage)
+                .before(check_level_completion)
+                .run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(OnExit(AppState::Playing), end_run_stats)
+        // level editor
+        .add_systems(
+            OnEnter(AppState::Editor),
+            (open_level_editor, spawn_level_editor_hud),
+        )
+        .add_systems(
+            Update,
+            (
+                level_editor_mouse_input,
+                level_editor_keyboard_input,
+                level_editor_file_input,
+                level_editor_exit,
+                draw_level_editor,
+                update_level_editor_hud,
+            )
+                .chain()
+                .run_if(in_state(AppState::Editor))
+                .run_if(resource_exists::<LevelEditor>()),
+        )
+        .add_systems(
+            OnExit(AppState::Editor),
+            (close_level_editor, despawn_level_editor_hud),
+        )
+        // camera
+        .add_systems(Startup, (spawn_camera, load_camera_settings))
+        .add_systems(
+            Update,
+            follow_players
+                .after(confine_player_movement)
+                .run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (add_camera_trauma, update_camera_transform)
+                .chain()
+                .after(follow_players),
+        )
+        .add_systems(Update, save_camera_settings)
         // init
-        .add_systems(Startup, spawn_player)
-        .add_systems(Startup, spawn_camera)
-        .add_systems(Startup, spawn_enemies)
-        .add_systems(Startup, spawn_stars)
+        .add_systems(
+            OnEnter(AppState::Playing),
+            (
+                reset_score,
+                reset_spawn_timers,
+                reset_boss_encounters,
+                reset_level_progress,
+            ),
+        )
+        // the level sizes the window the players spawn in,
+        // its obstacles are flushed before anything else looks for a free spot
+        .add_systems(
+            OnEnter(AppState::Playing),
+            (spawn_level, apply_deferred, spawn_player).chain(),
+        )
+        .add_systems(
+            OnExit(AppState::Playing),
+            (
+                despawn_players,
+                despawn_enemies,
+                despawn_projectiles,
+                despawn_player_projectiles,
+                despawn_obstacles,
+                despawn_stars,
+                despawn_boss_health_bar,
+                despawn_power_ups,
+                despawn_power_up_hud,
+                despawn_game_mode_hud,
+                despawn_near_miss_popups,
+                reset_enemy_time_scale,
+            ),
+        )
         // update
-        .add_systems(Update, player_movement)
-        .add_systems(Update, confine_player_movement)
-        .add_systems(Update, enemy_movement)
-        .add_systems(Update, update_enemy_direction)
-        .add_systems(Update, enemy_hit_player)
-        .add_systems(Update, player_hit_star)
-        // .add_systems(Update, confine_enemy_movement) // stick balls to the edges
-        .add_systems(Update, update_score)
-        .add_systems(Update, tick_star_spawn_timer)
-        .add_systems(Update, tick_enemy_spawn_timer)
-        .add_systems(Update, spawn_stars_over_time)
-        .add_systems(Update, spawn_enemies_over_time)
-        .add_systems(Update, exit_game)
-        .add_systems(Update, handle_game_over)
+        .add_systems(
+            Update,
+            (
+                player_movement,
+                confine_player_movement,
+                steer_enemies,
+                enemy_movement,
+                update_enemy_direction,
+                split_enemies,
+                fire_sniper_projectiles,
+                projectile_movement,
+                (enemy_hit_player, handle_player_damage).chain(),
+                blink_invulnerable_players,
+                (player_hit_star, apply_score_gains, update_score).chain(),
+                tick_combos,
+                expire_stars,
+                // confine_enemy_movement, // stick balls to the edges
+                tick_star_spawn_timer,
+                tick_enemy_spawn_timer,
+                spawn_stars_over_time,
+                spawn_enemies_over_time,
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        // level script and game mode rules, after the last life check so `GameOver` is sent once
+        .add_systems(
+            Update,
+            (
+                tick_level_clock,
+                release_waves,
+                check_level_completion.after(handle_player_damage),
+                apply_game_mode_rules,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(OnEnter(AppState::Playing), spawn_game_mode_hud)
+        .add_systems(
+            Update,
+            update_game_mode_hud.run_if(in_state(AppState::Playing)),
+        )
+        // near misses
+        .add_systems(
+            Update,
+            (
+                reward_near_misses
+                    .after(enemy_hit_player)
+                    .before(apply_score_gains),
+                animate_near_miss_popups,
+            )
+                .run_if(in_state(AppState::Playing)),
+        )
+        // obstacles
+        .add_systems(
+            Update,
+            (
+                bounce_enemies_off_obstacles.after(enemy_movement),
+                slide_players_along_obstacles
+                    .after(player_movement)
+                    .before(confine_player_movement),
+                obstacles_block_projectiles,
+            )
+                .run_if(in_state(AppState::Playing)),
+        )
+        // shooter mode, the fire button is a menu key while paused
+        .add_systems(
+            Update,
+            (
+                fire_player_projectiles,
+                player_projectile_movement,
+                player_projectile_hit_enemy,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        // population caps
+        .add_systems(
+            PostUpdate,
+            (
+                stamp_spawn_time,
+                enforce_population_caps,
+                update_entity_budget,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        )
+        // power-ups
+        .add_systems(OnEnter(AppState::Playing), spawn_power_up_hud)
+        .add_systems(
+            Update,
+            (
+                tick_power_up_spawn_timer,
+                spawn_power_ups_over_time,
+                player_hit_power_up,
+                tick_power_ups,
+                attract_stars,
+                update_enemy_time_scale,
+                update_power_up_hud,
+            )
+                .run_if(in_state(AppState::Playing)),
+        )
+        // bosses
+        .add_systems(
+            Update,
+            (
+                (damage_bosses, boss_attacks, defeat_bosses, spawn_bosses).chain(),
+                spawn_boss_health_bar,
+                update_boss_health_bar,
+                despawn_boss_health_bar.run_if(on_event::<BossDefeated>()),
+            )
+                .run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(
+            Update,
+            toggle_simulation
+                .run_if(in_state(AppState::Playing))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        .add_systems(
+            Update,
+            pause_on_focus_lost.run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (pause_menu_input, update_pause_menu_text).run_if(in_state(SimulationState::Paused)),
+        )
+        .add_systems(Update, handle_game_over.run_if(in_state(AppState::Playing)))
         .add_systems(Update, update_high_scores)
         .add_systems(Update, high_scores_updated)
+        // pause
+        .add_systems(
+            OnEnter(SimulationState::Paused),
+            (pause_simulation, spawn_pause_menu),
+        )
+        .add_systems(
+            OnExit(SimulationState::Paused),
+            (resume_simulation, despawn_pause_menu),
+        )
         // run
         .run();
 
@@ -0,0 +1,105 @@
+use crate::assets::RonAsset;
+use crate::resources::AchievementProgress;
+use crate::Result;
+use bevy::asset::Asset;
+use bevy::ecs::system::Resource;
+use bevy::reflect::TypePath;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum AchievementGoal {
+    /// Stars collected over every run
+    TotalStars(u32),
+    /// Enemy bounces over every run
+    TotalBounces(u32),
+    /// Runs played to the end
+    Runs(u32),
+    /// Seconds alive in a single run
+    SurviveSeconds(f32),
+    /// Team score of a single run before any player gets hurt
+    UntouchedScore(u32),
+    /// Enemy waves released in a single run
+    Waves(u32),
+}
+
+impl AchievementGoal {
+    /// Current value and target, lifetime goals show it on the achievements screen
+    pub fn progress(&self, progress: &AchievementProgress) -> (f32, f32) {
+        let run = &progress.run;
+        match self {
+            AchievementGoal::TotalStars(target) => (progress.total_stars as f32, *target as f32),
+            AchievementGoal::TotalBounces(target) => {
+                (progress.total_bounces as f32, *target as f32)
+            }
+            AchievementGoal::Runs(target) => (progress.runs as f32, *target as f32),
+            AchievementGoal::SurviveSeconds(target) => (run.seconds, *target),
+            AchievementGoal::UntouchedScore(target) => {
+                let score = if run.touched { 0 } else { run.score };
+                (score as f32, *target as f32)
+            }
+            AchievementGoal::Waves(target) => (run.waves as f32, *target as f32),
+        }
+    }
+
+    pub fn reached(&self, progress: &AchievementProgress) -> bool {
+        let (value, target) = self.progress(progress);
+        value >= target
+    }
+
+    /// Goals only meaningful under enemy pressure
+    pub fn needs_enemies(&self) -> bool {
+        matches!(
+            self,
+            AchievementGoal::SurviveSeconds(_) | AchievementGoal::UntouchedScore(_)
+        )
+    }
+
+    /// Goals counted over every run, the others have to be met within one
+    pub fn is_lifetime(&self) -> bool {
+        matches!(
+            self,
+            AchievementGoal::TotalStars(_)
+                | AchievementGoal::TotalBounces(_)
+                | AchievementGoal::Runs(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Achievement {
+    /// Key of the unlock in the saved progress
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub goal: AchievementGoal,
+}
+
+/// Every achievement in screen order, copied into a resource once loaded
+#[derive(Asset, TypePath, Resource, Debug, Clone, Deserialize)]
+pub struct AchievementCatalog {
+    pub achievements: Vec<Achievement>,
+}
+
+impl RonAsset for AchievementCatalog {
+    const EXTENSIONS: &'static [&'static str] = &["achievements.ron"];
+}
+
+impl AchievementCatalog {
+    pub fn get(&self, id: &str) -> Option<&Achievement> {
+        self.achievements
+            .iter()
+            .find(|achievement| achievement.id == id)
+    }
+
+    /// Saved progress refers to achievements by ID, they must be unique
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = BTreeSet::new();
+        for achievement in self.achievements.iter() {
+            if !ids.insert(achievement.id.as_str()) {
+                return Err(format!("duplicate achievement ID `{}`", achievement.id).into());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::asset::{io::Reader, Asset, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::reflect::TypePath;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// Data file deserialized as a whole by `RonAssetLoader`
+pub trait RonAsset: Asset + for<'de> Deserialize<'de> {
+    const EXTENSIONS: &'static [&'static str];
+}
+
+pub struct RonAssetLoader<A> {
+    _asset: PhantomData<A>,
+}
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self {
+            _asset: PhantomData,
+        }
+    }
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        A::EXTENSIONS
+    }
+}
+
+/// Logical IDs (`player.sprite`, `enemy.bounce`...) to files, so a re-skin is data only
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AssetManifest {
+    pub sprites: HashMap<String, String>,
+    /// Every variant of a sound, one is picked at random on every play
+    pub sounds: HashMap<String, Vec<String>>,
+    /// Looping tracks, every one of them is optional
+    #[serde(default)]
+    pub music: HashMap<String, String>,
+    /// Level files, in level-select order
+    #[serde(default)]
+    pub levels: Vec<String>,
+}
+
+impl RonAsset for AssetManifest {
+    const EXTENSIONS: &'static [&'static str] = &["assets.ron"];
+}
+
+impl AssetManifest {
+    pub fn sprite(&self, id: &str) -> Option<&str> {
+        self.sprites.get(id).map(String::as_str)
+    }
+
+    pub fn sound(&self, id: &str) -> Option<&[String]> {
+        self.sounds.get(id).map(Vec::as_slice)
+    }
+}
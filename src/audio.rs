@@ -0,0 +1,69 @@
+use crate::states::AppState;
+
+/// Sound effects played through the `PlaySfx` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    Explosion,
+    StarPickup,
+    Bounce,
+    PowerUp,
+    NearMiss,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 5] = [
+        Sfx::Explosion,
+        Sfx::StarPickup,
+        Sfx::Bounce,
+        Sfx::PowerUp,
+        Sfx::NearMiss,
+    ];
+
+    /// Logical ID of the sound in the asset manifest
+    pub fn id(&self) -> &'static str {
+        match self {
+            Sfx::Explosion => "player.explosion",
+            Sfx::StarPickup => "star.pickup",
+            Sfx::Bounce => "enemy.bounce",
+            Sfx::PowerUp => "power_up.pickup",
+            Sfx::NearMiss => "player.near_miss",
+        }
+    }
+}
+
+/// Looping track of an app state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicTrack {
+    Menu,
+    Playing,
+    GameOver,
+}
+
+impl MusicTrack {
+    pub fn from_state(app_state: AppState) -> Option<Self> {
+        match app_state {
+            // the editor stays silent
+            AppState::Loading | AppState::Editor => None,
+            AppState::MainMenu | AppState::Achievements | AppState::Stats => Some(MusicTrack::Menu),
+            AppState::Playing => Some(MusicTrack::Playing),
+            AppState::GameOver => Some(MusicTrack::GameOver),
+        }
+    }
+
+    /// Logical ID of the track in the asset manifest
+    pub fn id(&self) -> &'static str {
+        match self {
+            MusicTrack::Menu => "music.menu",
+            MusicTrack::Playing => "music.playing",
+            MusicTrack::GameOver => "music.game_over",
+        }
+    }
+
+    /// Layer played along the track, its volume follows `MusicIntensity`
+    pub fn intense_id(&self) -> Option<&'static str> {
+        match self {
+            MusicTrack::Playing => Some("music.playing.intense"),
+            _ => None,
+        }
+    }
+}
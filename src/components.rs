@@ -1,12 +1,444 @@
-use bevy::{ecs::component::Component, math::Vec2};
+use crate::obstacles::ObstacleShape;
+use crate::power_ups::PowerUpKind;
+use crate::{COMBO_MAX_MULTIPLIER, COMBO_STEP, COMBO_TIMEOUT};
+use bevy::{
+    ecs::{component::Component, entity::Entity},
+    math::Vec2,
+    render::color::Color,
+    time::{Timer, TimerMode},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PlayerId {
+    One,
+    Two,
+}
+
+impl PlayerId {
+    pub const ALL: [PlayerId; 2] = [PlayerId::One, PlayerId::Two];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerId::One => "Player 1",
+            PlayerId::Two => "Player 2",
+        }
+    }
+
+    /// Sprite tint telling the players apart
+    pub fn color(&self) -> Color {
+        match self {
+            PlayerId::One => Color::WHITE,
+            PlayerId::Two => Color::rgb(0.4, 1.0, 0.4),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Player {
+    pub id: PlayerId,
+}
+
+/// Hits left before losing a life
+#[derive(Component)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+#[derive(Component)]
+pub struct Lives {
+    pub remaining: u32,
+}
+
+/// Hits are ignored and the sprite blinks until `timer` finishes
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+    pub blink_timer: Timer,
+}
+
+/// Timed power-up effects running on a player
+#[derive(Component, Default)]
+pub struct ActivePowerUps {
+    pub timers: BTreeMap<PowerUpKind, Timer>,
+}
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.timers.contains_key(&kind)
+    }
+
+    /// Start the effect, or restart it when already running
+    pub fn activate(&mut self, kind: PowerUpKind, duration: f32) {
+        self.timers
+            .insert(kind, Timer::from_seconds(duration, TimerMode::Once));
+    }
+}
+
+/// Keyboard and gamepad movement merged, length is at most 1
+#[derive(Component, Default)]
+pub struct MovementAxis {
+    pub value: Vec2,
+}
+
+/// Shooting direction of a player, `firing` while the trigger is held
+#[derive(Component)]
+pub struct Aim {
+    pub direction: Vec2,
+    pub firing: bool,
+}
+
+impl Default for Aim {
+    fn default() -> Self {
+        Self {
+            direction: Vec2::Y,
+            firing: false,
+        }
+    }
+}
+
+/// Players only carry one in shooter mode
+#[derive(Component)]
+pub struct Weapon {
+    pub cooldown: Timer,
+}
 
 #[derive(Component)]
-pub struct Player {}
+pub struct PlayerProjectile {
+    pub owner: PlayerId,
+    pub velocity: Vec2,
+    pub lifetime: Timer,
+}
 
 #[derive(Component)]
 pub struct Enemy {
     pub direction: Vec2,
+    pub speed: f32,
+    /// Diameter of the collider
+    pub size: f32,
+    /// Shots left before destruction, bosses track theirs in `Boss`
+    pub health: u32,
+    /// Score for destroying it
+    pub points: u32,
+}
+
+#[derive(Component)]
+pub struct Splitter {
+    pub into: String,
+    pub count: u32,
+}
+
+#[derive(Component)]
+pub struct Sniper {
+    pub fire_timer: Timer,
+    pub projectile_speed: f32,
+}
+
+/// Boss of the catalog, also an `Enemy` for movement and collisions
+#[derive(Component)]
+pub struct Boss {
+    pub name: String,
+    pub health: u32,
+    pub max_health: u32,
+    pub phase: usize,
+    /// Index of the next attack in the phase rotation
+    pub next_attack: usize,
+    pub attack_timer: Timer,
+    /// Running charge, the boss goes back to its cruise speed when it finishes
+    pub charge_timer: Option<Timer>,
+    pub cruise_speed: f32,
+}
+
+impl Boss {
+    pub fn health_fraction(&self) -> f32 {
+        self.health as f32 / self.max_health.max(1) as f32
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyProjectile {
+    pub velocity: Vec2,
+}
+
+/// Static wall or obstacle, enemies bounce off it and players slide along it
+#[derive(Component)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StarKind {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl StarKind {
+    pub const ALL: [StarKind; 3] = [StarKind::Bronze, StarKind::Silver, StarKind::Gold];
+
+    /// Points before the combo multiplier
+    pub fn value(&self) -> u32 {
+        match self {
+            StarKind::Bronze => 1,
+            StarKind::Silver => 3,
+            StarKind::Gold => 10,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            StarKind::Bronze => Color::rgb(0.8, 0.5, 0.2),
+            StarKind::Silver => Color::rgb(0.8, 0.8, 0.9),
+            StarKind::Gold => Color::rgb(1.0, 0.85, 0.1),
+        }
+    }
+
+    /// Relative chance to be spawned
+    fn spawn_weight(&self) -> u32 {
+        match self {
+            StarKind::Bronze => 70,
+            StarKind::Silver => 25,
+            StarKind::Gold => 5,
+        }
+    }
+
+    pub fn random() -> Self {
+        let total_weight: u32 = Self::ALL.iter().map(StarKind::spawn_weight).sum();
+        let mut roll = rand::thread_rng().gen_range(0..total_weight);
+        for kind in Self::ALL {
+            if roll < kind.spawn_weight() {
+                return kind;
+            }
+            roll -= kind.spawn_weight();
+        }
+
+        StarKind::Bronze
+    }
+}
+
+/// Collectible, fades out and disappears when `lifetime` finishes
+#[derive(Component)]
+pub struct Star {
+    pub kind: StarKind,
+    pub lifetime: Timer,
+}
+
+/// Stars collected in quick succession, the streak is lost when `timer` finishes
+#[derive(Component)]
+pub struct Combo {
+    pub streak: u32,
+    pub timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            streak: 0,
+            timer: Timer::from_seconds(COMBO_TIMEOUT, TimerMode::Once),
+        }
+    }
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.streak / COMBO_STEP).min(COMBO_MAX_MULTIPLIER)
+    }
+
+    /// Extend the streak and restart the timeout
+    pub fn hit(&mut self) {
+        self.streak += 1;
+        self.timer.reset();
+    }
+}
+
+/// Enemies within the near-miss margin of a player, and whether they touched it
+#[derive(Component, Default)]
+pub struct NearMissTracker {
+    pub close_enemies: HashMap<Entity, bool>,
+}
+
+impl NearMissTracker {
+    /// Replace the close enemies with those of this frame,
+    /// returns the ones that left the margin without touching the player
+    pub fn update(&mut self, close_enemies: HashMap<Entity, bool>) -> Vec<Entity> {
+        let previous = std::mem::replace(&mut self.close_enemies, close_enemies);
+        let mut dodged = Vec::new();
+
+        for (enemy, touched) in previous {
+            match self.close_enemies.get_mut(&enemy) {
+                Some(still_touched) => *still_touched |= touched,
+                None if !touched => dodged.push(enemy),
+                None => {}
+            }
+        }
+        dodged
+    }
+}
+
+/// Floating "close!" text over a player, rises and fades until `timer` finishes
+#[derive(Component)]
+pub struct NearMissPopup {
+    pub timer: Timer,
+}
+
+/// Elapsed game time when the entity appeared, capped kinds get it on spawn
+#[derive(Component)]
+pub struct SpawnedAt {
+    pub seconds: f32,
+}
+
+#[derive(Component)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+#[derive(Component)]
+pub struct PauseMenu {}
+
+#[derive(Component)]
+pub struct PauseMenuText {}
+
+/// Sound effect being played, despawned when it finishes
+#[derive(Component)]
+pub struct SfxVoice {}
+
+#[derive(Component)]
+pub struct LoadingScreen {}
+
+#[derive(Component)]
+pub struct LoadingText {}
+
+#[derive(Component)]
+pub struct MainMenu {}
+
+#[derive(Component)]
+pub struct MainMenuText {}
+
+#[derive(Component)]
+pub struct LevelEditorHud {}
+
+#[derive(Component)]
+pub struct GameModeHud {}
+
+#[derive(Component)]
+pub struct GameOverScreen {}
+
+#[derive(Component)]
+pub struct AchievementsScreen {}
+
+#[derive(Component)]
+pub struct StatsScreen {}
+
+/// Unlock notice, despawned once its timer finishes
+#[derive(Component)]
+pub struct AchievementToast {
+    pub timer: Timer,
 }
 
+/// Active power-ups of every player
+#[derive(Component)]
+pub struct PowerUpHud {}
+
+#[derive(Component)]
+pub struct BossHealthBar {}
+
+/// Inner part of the health bar, its width follows the boss health
 #[derive(Component)]
-pub struct Star {}
+pub struct BossHealthBarFill {}
+
+/// Camera rig: follows the players, zooms out to keep them in view and shakes with trauma
+#[derive(Component)]
+pub struct CameraController {
+    /// Center of the view before the shake
+    pub focus: Vec2,
+    /// Projection scale, above 1.0 zooms out
+    pub zoom: f32,
+    /// From 0.0 to 1.0, decays over time
+    pub trauma: f32,
+}
+
+impl CameraController {
+    pub fn new(focus: Vec2) -> Self {
+        Self {
+            focus,
+            zoom: 1.0,
+            trauma: 0.0,
+        }
+    }
+
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    /// Shake strength, from 0.0 to 1.0
+    pub fn shake(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+}
+
+/// Looping music layer, faded in on spawn and faded out before despawn
+#[derive(Component)]
+pub struct MusicVoice {
+    pub intense: bool,
+    /// Crossfade gain, from 0.0 to 1.0
+    pub gain: f32,
+    pub fading_out: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combo_multiplier_grows_every_step_up_to_the_cap() {
+        let mut combo = Combo::default();
+        assert_eq!(combo.multiplier(), 1);
+
+        for _ in 0..COMBO_STEP - 1 {
+            combo.hit();
+        }
+        assert_eq!(combo.multiplier(), 1);
+        combo.hit();
+        assert_eq!(combo.multiplier(), 2);
+
+        combo.streak = COMBO_STEP * COMBO_MAX_MULTIPLIER * 2;
+        assert_eq!(combo.multiplier(), COMBO_MAX_MULTIPLIER);
+    }
+
+    #[test]
+    fn near_miss_tracker_reports_enemies_leaving_untouched() {
+        let dodger = Entity::from_raw(1);
+        let toucher = Entity::from_raw(2);
+        let lingerer = Entity::from_raw(3);
+        let mut tracker = NearMissTracker::default();
+
+        let first = HashMap::from([(dodger, false), (toucher, true), (lingerer, false)]);
+        assert!(tracker.update(first).is_empty());
+
+        // the lingerer stays close and touches the player, it will not count
+        let second = HashMap::from([(lingerer, true)]);
+        assert_eq!(tracker.update(second), vec![dodger]);
+
+        assert!(tracker.update(HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn near_miss_tracker_remembers_a_touch_while_still_close() {
+        let enemy = Entity::from_raw(1);
+        let mut tracker = NearMissTracker::default();
+
+        tracker.update(HashMap::from([(enemy, true)]));
+        tracker.update(HashMap::from([(enemy, false)]));
+        assert_eq!(tracker.close_enemies.get(&enemy), Some(&true));
+        assert!(tracker.update(HashMap::new()).is_empty());
+    }
+}
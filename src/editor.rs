@@ -0,0 +1,50 @@
+use crate::obstacles::ObstacleShape;
+
+/// What a click on empty space places
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EditorTool {
+    #[default]
+    Wall,
+    Pillar,
+    Enemy,
+    Star,
+}
+
+impl EditorTool {
+    /// In number key order
+    pub const ALL: [EditorTool; 4] = [
+        EditorTool::Wall,
+        EditorTool::Pillar,
+        EditorTool::Enemy,
+        EditorTool::Star,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorTool::Wall => "wall",
+            EditorTool::Pillar => "pillar",
+            EditorTool::Enemy => "enemy spawner",
+            EditorTool::Star => "star spawner",
+        }
+    }
+
+    /// Shape of a new obstacle, resized afterwards with the mouse wheel
+    pub fn obstacle_shape(&self) -> Option<ObstacleShape> {
+        match self {
+            EditorTool::Wall => Some(ObstacleShape::Rect {
+                half_width: 16.0,
+                half_height: 96.0,
+            }),
+            EditorTool::Pillar => Some(ObstacleShape::Circle { radius: 48.0 }),
+            EditorTool::Enemy | EditorTool::Star => None,
+        }
+    }
+}
+
+/// What dragging the held placement does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorDrag {
+    Move,
+    /// Point the initial direction of an enemy toward the cursor
+    Aim,
+}
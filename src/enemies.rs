@@ -0,0 +1,350 @@
+use crate::assets::RonAsset;
+use crate::steering::WeightedBehavior;
+use crate::Result;
+use bevy::asset::Asset;
+use bevy::ecs::system::Resource;
+use bevy::reflect::TypePath;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum EnemyBehavior {
+    /// Straight line, bounces on the walls
+    Bouncer,
+    /// Weighted steering behaviors, `speed` is the top speed
+    Steered {
+        max_acceleration: f32,
+        behaviors: Vec<WeightedBehavior>,
+    },
+    /// Replaced by `count` enemies of type `into` on a wall hit
+    Splitter { into: String, count: u32 },
+    /// Fires a projectile toward the closest player every `fire_interval` seconds
+    Sniper {
+        fire_interval: f32,
+        projectile_speed: f32,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyType {
+    /// Sprite ID in the asset manifest
+    pub sprite: String,
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 3],
+    pub speed: f32,
+    /// Diameter of both the sprite and the collider
+    pub size: f32,
+    /// Relative chance to be picked by the spawners, 0.0 for split-only types
+    #[serde(default)]
+    pub spawn_weight: f32,
+    /// Shots needed to destroy it in shooter mode
+    #[serde(default = "default_health")]
+    pub health: u32,
+    /// Score for destroying it
+    #[serde(default = "default_points")]
+    pub points: u32,
+    pub behavior: EnemyBehavior,
+}
+
+fn default_tint() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_health() -> u32 {
+    1
+}
+
+fn default_points() -> u32 {
+    2
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum BossAttack {
+    /// Ring of `projectiles` fired all around the boss
+    Ring { projectiles: u32, speed: f32 },
+    /// Rush toward the closest player at `speed` for `duration` seconds
+    Charge { speed: f32, duration: f32 },
+    /// Release `count` enemies of type `enemy` around the boss
+    SpawnMinions { enemy: String, count: u32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossPhase {
+    /// The phase starts once the health fraction drops to this value, 1.0 for the first one
+    pub below_health: f32,
+    /// Seconds between two attacks
+    pub interval: f32,
+    /// Attacks used in turn
+    pub attacks: Vec<BossAttack>,
+}
+
+/// Threshold bringing in a boss, once per run
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum BossTrigger {
+    /// Team score to reach
+    Score(u32),
+    /// Waves of the level released
+    Wave(u32),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossType {
+    /// Sprite ID in the asset manifest
+    pub sprite: String,
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 3],
+    pub speed: f32,
+    pub size: f32,
+    pub health: u32,
+    /// The boss shows up on the first one reached
+    pub triggers: Vec<BossTrigger>,
+    /// Points given to every surviving player on defeat
+    pub reward: u32,
+    pub phases: Vec<BossPhase>,
+}
+
+impl BossType {
+    /// Index of the phase matching the health fraction
+    pub fn phase_index(&self, health_fraction: f32) -> usize {
+        self.phases
+            .iter()
+            .rposition(|phase| health_fraction <= phase.below_health)
+            .unwrap_or(0)
+    }
+}
+
+/// Every enemy type by name, copied into a resource once loaded
+#[derive(Asset, TypePath, Resource, Debug, Clone, Deserialize)]
+pub struct EnemyCatalog {
+    pub types: BTreeMap<String, EnemyType>,
+    #[serde(default)]
+    pub bosses: BTreeMap<String, BossType>,
+}
+
+impl RonAsset for EnemyCatalog {
+    const EXTENSIONS: &'static [&'static str] = &["enemies.ron"];
+}
+
+impl EnemyCatalog {
+    pub fn get(&self, name: &str) -> Option<&EnemyType> {
+        self.types.get(name)
+    }
+
+    pub fn boss(&self, name: &str) -> Option<&BossType> {
+        self.bosses.get(name)
+    }
+
+    /// Every splitter must split into a known type and end up with non-splitters,
+    /// steering radii must be positive and every boss summon known minions
+    pub fn validate(&self) -> Result<()> {
+        for (name, enemy_type) in self.types.iter() {
+            if let EnemyBehavior::Steered { behaviors, .. } = &enemy_type.behavior {
+                let radii = behaviors
+                    .iter()
+                    .filter_map(|weighted| weighted.behavior.radius());
+                for radius in radii {
+                    if radius <= 0.0 || radius.is_nan() {
+                        return Err(format!(
+                            "enemy type `{name}` steers with a non-positive radius {radius}"
+                        )
+                        .into());
+                    }
+                }
+            }
+
+            if let EnemyBehavior::Splitter { into, .. } = &enemy_type.behavior {
+                if !self.types.contains_key(into) {
+                    return Err(
+                        format!("enemy type `{name}` splits into unknown type `{into}`").into(),
+                    );
+                }
+
+                // a split chain coming back to a type would never end
+                let mut chain = BTreeSet::from([name.as_str()]);
+                let mut next = into;
+                loop {
+                    if !chain.insert(next.as_str()) {
+                        return Err(format!("enemy type `{name}` splits back into `{next}`").into());
+                    }
+                    match self.types.get(next).map(|enemy_type| &enemy_type.behavior) {
+                        Some(EnemyBehavior::Splitter { into, .. }) => next = into,
+                        _ => break,
+                    }
+                }
+            }
+        }
+
+        for (name, boss_type) in self.bosses.iter() {
+            if boss_type.phases.is_empty() {
+                return Err(format!("boss `{name}` has no phase").into());
+            }
+            if boss_type.triggers.is_empty() {
+                return Err(format!("boss `{name}` has no trigger").into());
+            }
+            for phase in boss_type.phases.iter() {
+                for attack in phase.attacks.iter() {
+                    if let BossAttack::SpawnMinions { enemy, .. } = attack {
+                        if !self.types.contains_key(enemy) {
+                            return Err(format!(
+                                "boss `{name}` spawns unknown enemy type `{enemy}`"
+                            )
+                            .into());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// First boss not met yet in this run with a trigger reached by `score` or `waves`
+    pub fn next_boss<'a>(
+        &'a self,
+        score: u32,
+        waves: u32,
+        encountered: &BTreeSet<String>,
+    ) -> Option<&'a str> {
+        self.bosses
+            .iter()
+            .find(|(name, boss_type)| {
+                !encountered.contains(*name)
+                    && boss_type.triggers.iter().any(|trigger| match trigger {
+                        BossTrigger::Score(threshold) => score >= *threshold,
+                        BossTrigger::Wave(threshold) => waves >= *threshold,
+                    })
+            })
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Weighted pick among the spawnable types of `allowed`, every type when it is empty
+    pub fn pick_random(&self, allowed: &[String]) -> Option<&str> {
+        let candidates = self
+            .types
+            .iter()
+            .filter(|(name, enemy_type)| {
+                enemy_type.spawn_weight > 0.0 && (allowed.is_empty() || allowed.contains(name))
+            })
+            .collect::<Vec<_>>();
+        let total_weight: f32 = candidates.iter().map(|(_, t)| t.spawn_weight).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut roll = rand::thread_rng().gen_range(0.0..total_weight);
+        for (name, enemy_type) in candidates.iter() {
+            roll -= enemy_type.spawn_weight;
+            if roll < 0.0 {
+                return Some(name);
+            }
+        }
+
+        candidates.last().map(|(name, _)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TYPES: &str = r#"
+        "bouncer": (sprite: "enemy", speed: 100.0, size: 32.0, behavior: Bouncer),
+        "splitter": (
+            sprite: "enemy",
+            speed: 100.0,
+            size: 48.0,
+            behavior: Splitter(into: "bouncer", count: 2),
+        ),
+    "#;
+
+    fn catalog(types: &str, bosses: &str) -> EnemyCatalog {
+        ron::from_str(&format!(
+            "(types: {{ {TYPES} {types} }}, bosses: {{ {bosses} }})"
+        ))
+        .unwrap()
+    }
+
+    fn boss(triggers: &str, attacks: &str) -> String {
+        format!(
+            r#"(
+                sprite: "boss",
+                speed: 80.0,
+                size: 128.0,
+                health: 20,
+                triggers: [{triggers}],
+                reward: 10,
+                phases: [(below_health: 1.0, interval: 2.0, attacks: [{attacks}])],
+            )"#
+        )
+    }
+
+    #[test]
+    fn validate_accepts_a_sound_catalog() {
+        let warden = boss("Score(25)", r#"SpawnMinions(enemy: "bouncer", count: 2)"#);
+        assert!(catalog("", &format!(r#""warden": {warden}"#))
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_and_cyclic_splits() {
+        let unknown = r#""loose": (sprite: "enemy", speed: 1.0, size: 1.0,
+            behavior: Splitter(into: "ghost", count: 2)),"#;
+        assert!(catalog(unknown, "").validate().is_err());
+
+        let cycle = r#"
+            "ping": (sprite: "enemy", speed: 1.0, size: 1.0, behavior: Splitter(into: "pong", count: 2)),
+            "pong": (sprite: "enemy", speed: 1.0, size: 1.0, behavior: Splitter(into: "ping", count: 2)),
+        "#;
+        assert!(catalog(cycle, "").validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_steering_radii() {
+        let steered = |radius: &str| {
+            format!(
+                r#""chaser": (sprite: "enemy", speed: 1.0, size: 1.0, behavior: Steered(
+                    max_acceleration: 1.0,
+                    behaviors: [(behavior: Separation(radius: {radius}))],
+                )),"#
+            )
+        };
+        assert!(catalog(&steered("96.0"), "").validate().is_ok());
+        assert!(catalog(&steered("0.0"), "").validate().is_err());
+        assert!(catalog(&steered("-1.0"), "").validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_broken_bosses() {
+        let no_trigger = boss("", "Ring(projectiles: 8, speed: 100.0)");
+        assert!(catalog("", &format!(r#""warden": {no_trigger}"#))
+            .validate()
+            .is_err());
+
+        let unknown_minion = boss("Wave(2)", r#"SpawnMinions(enemy: "ghost", count: 2)"#);
+        assert!(catalog("", &format!(r#""warden": {unknown_minion}"#))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn next_boss_waits_for_a_trigger_and_shows_up_once() {
+        let attack = "Ring(projectiles: 8, speed: 100.0)";
+        let bosses = format!(
+            r#""colossus": {}, "warden": {}"#,
+            boss("Score(100)", attack),
+            boss("Score(25), Wave(4)", attack)
+        );
+        let catalog = catalog("", &bosses);
+        let mut encountered = BTreeSet::new();
+
+        assert_eq!(catalog.next_boss(10, 1, &encountered), None);
+        assert_eq!(catalog.next_boss(10, 4, &encountered), Some("warden"));
+        assert_eq!(catalog.next_boss(30, 0, &encountered), Some("warden"));
+
+        encountered.insert("warden".to_string());
+        assert_eq!(catalog.next_boss(30, 4, &encountered), None);
+        assert_eq!(catalog.next_boss(100, 4, &encountered), Some("colossus"));
+    }
+}
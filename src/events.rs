@@ -1,6 +1,130 @@
-use bevy::ecs::event::Event;
+use crate::audio::Sfx;
+use crate::components::{PlayerId, StarKind};
+use crate::resources::{LocalPlayers, RunStats, Score, Stats};
+use bevy::{
+    ecs::{entity::Entity, event::Event},
+    math::Vec2,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Event)]
 pub struct GameOver {
+    /// Team score, sum of every player score
     pub score: u32,
+    pub player_scores: BTreeMap<PlayerId, u32>,
+    /// Metrics of the run that ended
+    pub summary: RunStats,
+}
+
+impl GameOver {
+    pub fn new(score: &Score, local_players: &LocalPlayers, stats: &Stats) -> Self {
+        Self {
+            score: score.total(),
+            player_scores: local_players
+                .players
+                .iter()
+                .map(|player_id| (*player_id, score.get(*player_id)))
+                .collect(),
+            summary: stats.run.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ScoreReason {
+    Star { kind: StarKind, multiplier: u32 },
+    BossDefeated { name: String },
+    EnemyDestroyed,
+    NearMiss,
+}
+
+/// Points earned by a player, only `apply_score_gains` writes them to `Score`
+#[derive(Event)]
+pub struct ScoreGained {
+    pub player_id: PlayerId,
+    pub amount: u32,
+    pub reason: ScoreReason,
+}
+
+/// What hurt a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DamageCause {
+    Enemy,
+    Boss,
+    Projectile,
+}
+
+impl DamageCause {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DamageCause::Enemy => "enemy",
+            DamageCause::Boss => "boss",
+            DamageCause::Projectile => "projectile",
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct PlayerDamaged {
+    pub player: Entity,
+    pub player_id: PlayerId,
+    pub cause: DamageCause,
+    /// Health left, a life is lost at 0
+    pub health: u32,
+    pub position: Vec2,
+}
+
+/// An enemy passed within `NEAR_MISS_MARGIN` of a player without touching it
+#[derive(Event)]
+pub struct NearMiss {
+    pub player: Entity,
+    pub player_id: PlayerId,
+    pub position: Vec2,
+}
+
+#[derive(Event)]
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    /// World position of the emitter, panned and attenuated around the listener,
+    /// `None` plays it centered
+    pub position: Option<Vec2>,
+}
+
+/// Trauma added to the camera, on hits and explosions
+#[derive(Event)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+#[derive(Event)]
+pub struct EnemyBounced {
+    pub enemy: Entity,
+    pub position: Vec2,
+}
+
+#[derive(Event)]
+pub struct BossSpawned {
+    pub name: String,
+}
+
+#[derive(Event)]
+pub struct BossDefeated {
+    pub name: String,
+    /// Points given to every surviving player
+    pub reward: u32,
+}
+
+#[derive(Event)]
+pub struct WaveReleased {
+    /// Index in the waves of the level
+    pub index: usize,
+    pub enemy: String,
+    pub count: u32,
+}
+
+#[derive(Event)]
+pub struct AchievementUnlocked {
+    /// ID in the achievement catalog
+    pub id: String,
 }
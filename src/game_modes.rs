@@ -0,0 +1,93 @@
+use crate::{
+    HARDCORE_ENEMY_SPEED_FACTOR, HARDCORE_LIVES, PLAYER_LIVES, TARGET_SCORE_STARS,
+    TIME_ATTACK_DURATION, ZEN_DURATION,
+};
+use bevy::ecs::system::Resource;
+
+/// Rules of a run, picked in the main menu, every mode has its own leaderboard
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GameMode {
+    /// Survive until the last life is lost
+    #[default]
+    Endless,
+    /// Collect as many stars as possible before the time runs out
+    TimeAttack,
+    /// Collect a number of stars as fast as possible
+    TargetScore,
+    /// Stars only, no enemy, for a fixed time
+    Zen,
+    /// A single life against faster enemies
+    Hardcore,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Endless,
+        GameMode::TimeAttack,
+        GameMode::TargetScore,
+        GameMode::Zen,
+        GameMode::Hardcore,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Endless => "Endless",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::TargetScore => "Target Score",
+            GameMode::Zen => "Zen",
+            GameMode::Hardcore => "Hardcore",
+        }
+    }
+
+    /// Seconds before the run ends
+    pub fn time_limit(&self) -> Option<f32> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_DURATION),
+            GameMode::Zen => Some(ZEN_DURATION),
+            _ => None,
+        }
+    }
+
+    /// Stars the team collects to finish the run
+    pub fn target_stars(&self) -> Option<u32> {
+        match self {
+            GameMode::TargetScore => Some(TARGET_SCORE_STARS),
+            _ => None,
+        }
+    }
+
+    /// False keeps every enemy and boss out of the arena
+    pub fn has_enemies(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    pub fn lives(&self) -> u32 {
+        match self {
+            GameMode::Hardcore => HARDCORE_LIVES,
+            _ => PLAYER_LIVES,
+        }
+    }
+
+    /// Speed factor of enemies and their projectiles
+    pub fn enemy_speed_factor(&self) -> f32 {
+        match self {
+            GameMode::Hardcore => HARDCORE_ENEMY_SPEED_FACTOR,
+            _ => 1.0,
+        }
+    }
+
+    /// Leaderboard order, the fastest finished runs first instead of the best scores
+    pub fn ranks_by_time(&self) -> bool {
+        *self == GameMode::TargetScore
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
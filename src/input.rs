@@ -0,0 +1,126 @@
+use crate::components::PlayerId;
+use bevy::input::{gamepad::GamepadButtonType, keyboard::KeyCode};
+use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Game intents, gameplay systems read these instead of raw keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Pause,
+    Confirm,
+    /// Shooter mode only
+    Fire,
+    /// Menus only, flips the setting they offer
+    Toggle,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Pause,
+        Action::Confirm,
+        Action::Fire,
+        Action::Toggle,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+}
+
+impl KeyboardLayout {
+    /// Default key profile of a player for this layout, the first player also gets the arrows
+    pub fn default_bindings(&self, player_id: PlayerId) -> BTreeMap<Action, Vec<KeyCode>> {
+        match player_id {
+            PlayerId::One => {
+                let (up, down, left, right) = match self {
+                    KeyboardLayout::Qwerty => (KeyCode::W, KeyCode::S, KeyCode::A, KeyCode::D),
+                    KeyboardLayout::Azerty => (KeyCode::Z, KeyCode::S, KeyCode::Q, KeyCode::D),
+                };
+
+                BTreeMap::from([
+                    (Action::MoveUp, vec![KeyCode::Up, up]),
+                    (Action::MoveDown, vec![KeyCode::Down, down]),
+                    (Action::MoveLeft, vec![KeyCode::Left, left]),
+                    (Action::MoveRight, vec![KeyCode::Right, right]),
+                    (Action::Pause, vec![KeyCode::Escape]),
+                    (Action::Confirm, vec![KeyCode::Return]),
+                    (Action::Fire, vec![KeyCode::Space]),
+                    (Action::Toggle, vec![KeyCode::Tab]),
+                ])
+            }
+            // I, J, K and L sit at the same place on both layouts
+            PlayerId::Two => BTreeMap::from([
+                (Action::MoveUp, vec![KeyCode::I]),
+                (Action::MoveDown, vec![KeyCode::K]),
+                (Action::MoveLeft, vec![KeyCode::J]),
+                (Action::MoveRight, vec![KeyCode::L]),
+                (Action::Pause, vec![]),
+                (Action::Confirm, vec![]),
+                (Action::Fire, vec![KeyCode::U]),
+                (Action::Toggle, vec![]),
+            ]),
+        }
+    }
+}
+
+pub fn default_gamepad_bindings() -> BTreeMap<Action, Vec<GamepadButtonType>> {
+    BTreeMap::from([
+        (Action::MoveUp, vec![GamepadButtonType::DPadUp]),
+        (Action::MoveDown, vec![GamepadButtonType::DPadDown]),
+        (Action::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+        (Action::MoveRight, vec![GamepadButtonType::DPadRight]),
+        (Action::Pause, vec![GamepadButtonType::Start]),
+        (Action::Confirm, vec![GamepadButtonType::South]),
+        (Action::Fire, vec![GamepadButtonType::RightTrigger2]),
+        (Action::Toggle, vec![GamepadButtonType::North]),
+    ])
+}
+
+/// Zero the stick inside `deadzone` and rescale the rest so speed ramps up from 0 to 1
+pub fn apply_radial_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+
+    stick / length * ((length - deadzone) / (1.0 - deadzone)).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radial_deadzone_zeroes_the_center() {
+        assert_eq!(apply_radial_deadzone(Vec2::new(0.1, 0.1), 0.2), Vec2::ZERO);
+        assert_eq!(apply_radial_deadzone(Vec2::new(0.2, 0.0), 0.2), Vec2::ZERO);
+    }
+
+    #[test]
+    fn radial_deadzone_ramps_up_to_the_whole_stick() {
+        let halfway = apply_radial_deadzone(Vec2::new(0.0, 0.6), 0.2);
+        assert!((halfway - Vec2::new(0.0, 0.5)).length() < 1e-5);
+
+        let full = apply_radial_deadzone(Vec2::new(-1.0, 0.0), 0.2);
+        assert!((full - Vec2::NEG_X).length() < 1e-5);
+    }
+
+    #[test]
+    fn radial_deadzone_keeps_the_direction_and_caps_the_length() {
+        let corner = apply_radial_deadzone(Vec2::new(1.0, 1.0), 0.2);
+        assert!((corner.length() - 1.0).abs() < 1e-5);
+        assert!((corner.x - corner.y).abs() < 1e-5);
+    }
+}
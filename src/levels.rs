@@ -0,0 +1,350 @@
+use crate::assets::RonAsset;
+use crate::enemies::EnemyCatalog;
+use crate::obstacles::ObstacleShape;
+use crate::{Result, PLAYER_SIZE, STAR_SIZE};
+use bevy::asset::Asset;
+use bevy::ecs::system::Resource;
+use bevy::math::Vec2;
+use bevy::reflect::TypePath;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObstaclePlacement {
+    pub position: [f32; 2],
+    pub shape: ObstacleShape,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnemyPlacement {
+    /// Enemy type name in the catalog
+    pub enemy: String,
+    pub position: [f32; 2],
+    /// Random heading when omitted
+    #[serde(default)]
+    pub direction: Option<[f32; 2]>,
+}
+
+/// `count` enemies of type `enemy` released at random free spots `at` seconds into the level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wave {
+    pub at: f32,
+    pub enemy: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WinCondition {
+    /// Team score to reach
+    Score(u32),
+    /// Seconds to stay alive
+    Survive(f32),
+    /// Every wave released and no enemy left
+    ClearWaves,
+    /// Boss of the catalog to defeat
+    DefeatBoss(String),
+}
+
+/// Arena layout and script of a level, positions are in pixels from the bottom-left corner
+#[derive(Asset, TypePath, Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    /// Width and height, the window is resized to it
+    pub arena: [f32; 2],
+    /// Music ID in the asset manifest for the base layer, `music.playing` when omitted
+    #[serde(default)]
+    pub music: Option<String>,
+    #[serde(default)]
+    pub obstacles: Vec<ObstaclePlacement>,
+    #[serde(default)]
+    pub enemies: Vec<EnemyPlacement>,
+    #[serde(default)]
+    pub stars: Vec<[f32; 2]>,
+    /// Types picked by the random enemy spawner, every spawnable type when empty
+    #[serde(default)]
+    pub enemy_types: Vec<String>,
+    /// Enemies of `enemy_types` keep showing up every few seconds
+    #[serde(default = "default_random_enemies")]
+    pub random_enemies: bool,
+    /// Sorted by time
+    #[serde(default)]
+    pub waves: Vec<Wave>,
+    /// The level is won once every condition holds, endless without any
+    #[serde(default)]
+    pub win_conditions: Vec<WinCondition>,
+}
+
+/// Placement of a level, by index in its list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelItem {
+    Obstacle(usize),
+    Enemy(usize),
+    Star(usize),
+}
+
+fn default_random_enemies() -> bool {
+    true
+}
+
+impl RonAsset for Level {
+    const EXTENSIONS: &'static [&'static str] = &["level.ron"];
+}
+
+impl Level {
+    pub fn size(&self) -> Vec2 {
+        Vec2::from(self.arena)
+    }
+
+    /// Placements must fit in the arena clear of the obstacles,
+    /// and every enemy type and boss must be in the catalog
+    pub fn validate(&self, enemy_catalog: &EnemyCatalog) -> Result<()> {
+        let name = &self.name;
+        let size = self.size();
+        // players respawn at least `PLAYER_SIZE` away from every side
+        let min_size = PLAYER_SIZE * 2.0;
+        if size.x < min_size || size.y < min_size {
+            return Err(format!(
+                "level `{name}` has an arena smaller than {min_size} x {min_size}"
+            )
+            .into());
+        }
+
+        let positions = self
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.position)
+            .chain(self.enemies.iter().map(|enemy| enemy.position))
+            .chain(self.stars.iter().copied());
+        for [x, y] in positions {
+            if !(0.0..=size.x).contains(&x) || !(0.0..=size.y).contains(&y) {
+                return Err(format!(
+                    "level `{name}` places something out of the arena at ({x}, {y})"
+                )
+                .into());
+            }
+        }
+
+        let clearances = self
+            .enemies
+            .iter()
+            .map(|enemy| {
+                let radius = enemy_catalog
+                    .get(&enemy.enemy)
+                    .map_or(0.0, |enemy_type| enemy_type.size / 2.0);
+                (enemy.position, radius)
+            })
+            .chain(self.stars.iter().map(|star| (*star, STAR_SIZE / 2.0)));
+        for (position, radius) in clearances {
+            let blocked = self.obstacles.iter().any(|obstacle| {
+                obstacle
+                    .shape
+                    .contact(Vec2::from(obstacle.position), Vec2::from(position), radius)
+                    .is_some()
+            });
+            if blocked {
+                let [x, y] = position;
+                return Err(format!(
+                    "level `{name}` places something inside an obstacle at ({x}, {y})"
+                )
+                .into());
+            }
+        }
+
+        let enemy_types = self
+            .enemies
+            .iter()
+            .map(|enemy| &enemy.enemy)
+            .chain(self.waves.iter().map(|wave| &wave.enemy))
+            .chain(self.enemy_types.iter());
+        for enemy in enemy_types {
+            if enemy_catalog.get(enemy).is_none() {
+                return Err(format!("level `{name}` uses unknown enemy type `{enemy}`").into());
+            }
+        }
+
+        if self.waves.windows(2).any(|waves| waves[0].at > waves[1].at) {
+            return Err(format!("level `{name}` has waves out of order").into());
+        }
+
+        for condition in self.win_conditions.iter() {
+            if let WinCondition::DefeatBoss(boss) = condition {
+                if enemy_catalog.boss(boss).is_none() {
+                    return Err(format!("level `{name}` waits for unknown boss `{boss}`").into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// region:			--- Editing
+
+impl Level {
+    /// Topmost placement under `point`, stars over enemies over obstacles
+    pub fn item_at(&self, point: Vec2, enemy_catalog: &EnemyCatalog) -> Option<LevelItem> {
+        let star = || {
+            self.stars
+                .iter()
+                .rposition(|star| Vec2::from(*star).distance(point) <= STAR_SIZE / 2.0)
+                .map(LevelItem::Star)
+        };
+        let enemy = || {
+            self.enemies
+                .iter()
+                .rposition(|enemy| {
+                    let radius = enemy_catalog
+                        .get(&enemy.enemy)
+                        .map_or(0.0, |enemy_type| enemy_type.size / 2.0);
+                    Vec2::from(enemy.position).distance(point) <= radius
+                })
+                .map(LevelItem::Enemy)
+        };
+        let obstacle = || {
+            self.obstacles
+                .iter()
+                .rposition(|obstacle| {
+                    obstacle
+                        .shape
+                        .contact(Vec2::from(obstacle.position), point, 0.0)
+                        .is_some()
+                })
+                .map(LevelItem::Obstacle)
+        };
+
+        star().or_else(enemy).or_else(obstacle)
+    }
+
+    pub fn item_position(&self, item: LevelItem) -> Option<Vec2> {
+        let position = match item {
+            LevelItem::Obstacle(index) => self.obstacles.get(index)?.position,
+            LevelItem::Enemy(index) => self.enemies.get(index)?.position,
+            LevelItem::Star(index) => *self.stars.get(index)?,
+        };
+        Some(Vec2::from(position))
+    }
+
+    pub fn set_item_position(&mut self, item: LevelItem, position: Vec2) {
+        let slot = match item {
+            LevelItem::Obstacle(index) => self.obstacles.get_mut(index).map(|o| &mut o.position),
+            LevelItem::Enemy(index) => self.enemies.get_mut(index).map(|e| &mut e.position),
+            LevelItem::Star(index) => self.stars.get_mut(index),
+        };
+        if let Some(slot) = slot {
+            *slot = position.to_array();
+        }
+    }
+
+    /// Remove a placement, the following ones of its list shift down by one
+    pub fn remove_item(&mut self, item: LevelItem) {
+        match item {
+            LevelItem::Obstacle(index) if index < self.obstacles.len() => {
+                self.obstacles.remove(index);
+            }
+            LevelItem::Enemy(index) if index < self.enemies.len() => {
+                self.enemies.remove(index);
+            }
+            LevelItem::Star(index) if index < self.stars.len() => {
+                self.stars.remove(index);
+            }
+            _ => {}
+        }
+    }
+}
+
+// endregion:		--- Editing
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enemy_catalog() -> EnemyCatalog {
+        ron::from_str(
+            r#"(
+                types: {
+                    "bouncer": (sprite: "enemy", speed: 100.0, size: 32.0, behavior: Bouncer),
+                },
+                bosses: {
+                    "warden": (
+                        sprite: "boss",
+                        speed: 80.0,
+                        size: 128.0,
+                        health: 20,
+                        triggers: [Score(25)],
+                        reward: 10,
+                        phases: [(below_health: 1.0, interval: 2.0, attacks: [])],
+                    ),
+                },
+            )"#,
+        )
+        .unwrap()
+    }
+
+    fn level(fields: &str) -> Level {
+        ron::from_str(&format!(
+            r#"(name: "Test", arena: (800.0, 600.0), {fields})"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn validate_accepts_a_sound_level() {
+        let level = level(
+            r#"
+            obstacles: [(position: (400.0, 300.0), shape: Circle(radius: 40.0))],
+            enemies: [(enemy: "bouncer", position: (100.0, 100.0))],
+            stars: [(700.0, 500.0)],
+            waves: [(at: 5.0, enemy: "bouncer", count: 2), (at: 10.0, enemy: "bouncer", count: 3)],
+            win_conditions: [ClearWaves, DefeatBoss("warden")],
+            "#,
+        );
+        assert!(level.validate(&enemy_catalog()).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_arenas_too_small_to_spawn_a_player() {
+        let catalog = enemy_catalog();
+        let mut level = level("");
+
+        level.arena = [0.0, 600.0];
+        assert!(level.validate(&catalog).is_err());
+        level.arena = [800.0, PLAYER_SIZE * 2.0 - 1.0];
+        assert!(level.validate(&catalog).is_err());
+        level.arena = [PLAYER_SIZE * 2.0, PLAYER_SIZE * 2.0];
+        assert!(level.validate(&catalog).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_placements_out_of_the_arena_or_in_obstacles() {
+        let catalog = enemy_catalog();
+        assert!(level("stars: [(900.0, 300.0)]").validate(&catalog).is_err());
+
+        let buried = level(
+            r#"
+            obstacles: [(position: (400.0, 300.0), shape: Rect(half_width: 50.0, half_height: 50.0))],
+            stars: [(410.0, 300.0)],
+            "#,
+        );
+        assert!(buried.validate(&catalog).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_enemies_and_bosses() {
+        let catalog = enemy_catalog();
+        let unknown_enemy = level(r#"enemies: [(enemy: "ghost", position: (100.0, 100.0))]"#);
+        assert!(unknown_enemy.validate(&catalog).is_err());
+
+        let unknown_wave = level(r#"waves: [(at: 1.0, enemy: "ghost", count: 1)]"#);
+        assert!(unknown_wave.validate(&catalog).is_err());
+
+        let unknown_boss = level(r#"win_conditions: [DefeatBoss("colossus")]"#);
+        assert!(unknown_boss.validate(&catalog).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_waves_out_of_order() {
+        let level = level(
+            r#"waves: [(at: 10.0, enemy: "bouncer", count: 1), (at: 5.0, enemy: "bouncer", count: 1)]"#,
+        );
+        assert!(level.validate(&enemy_catalog()).is_err());
+    }
+}
@@ -1,7 +1,21 @@
+pub mod achievements;
+pub mod assets;
+pub mod audio;
 pub mod components;
+pub mod editor;
+pub mod enemies;
 mod error;
 pub mod events;
+pub mod game_modes;
+pub mod input;
+pub mod levels;
+pub mod obstacles;
+pub mod population;
+pub mod power_ups;
 pub mod resources;
+mod settings;
+pub mod states;
+pub mod steering;
 pub mod systems;
 
 pub use self::error::{Error, Result};
@@ -11,16 +25,105 @@ pub use self::error::{Error, Result};
 // player
 const PLAYER_SIZE: f32 = 64.0;
 const PLAYER_SPEED: f32 = 500.0;
+const PLAYER_HEALTH: u32 = 3;
+const PLAYER_LIVES: u32 = 3;
+const INVULNERABILITY_TIME: f32 = 1.5;
+const INVULNERABILITY_BLINK_TIME: f32 = 0.1;
+const PLAYER_FIRE_INTERVAL: f32 = 0.2;
+const PLAYER_PROJECTILE_SPEED: f32 = 700.0;
+const PLAYER_PROJECTILE_LIFETIME: f32 = 1.2;
 
 // enemies
-const NUMBER_OF_ENEMIES: usize = 4;
-const ENEMY_SIZE: f32 = 64.0;
-const ENEMY_SPEED: f32 = 200.0;
 const ENEMY_SPAWN_TIME: f32 = 5.0;
+const PROJECTILE_SIZE: f32 = 16.0;
 
 // stars
-const NUMBER_OF_STARS: usize = 10;
 const STAR_SIZE: f32 = 30.0;
 const STAR_SPAWN_TIME: f32 = 1.0;
+const STAR_LIFETIME: f32 = 8.0;
+// stars fade out over the end of their lifetime
+const STAR_FADE_TIME: f32 = 2.0;
+const COMBO_TIMEOUT: f32 = 1.5;
+// the multiplier grows by one every COMBO_STEP stars of a streak
+const COMBO_STEP: u32 = 3;
+const COMBO_MAX_MULTIPLIER: u32 = 5;
+
+// near misses
+// gap between an enemy and a player that still counts as a near miss
+const NEAR_MISS_MARGIN: f32 = 24.0;
+const NEAR_MISS_POINTS: u32 = 2;
+const NEAR_MISS_POPUP_TIME: f32 = 0.8;
+
+// power-ups
+const POWER_UP_SIZE: f32 = 32.0;
+const POWER_UP_SPAWN_TIME: f32 = 10.0;
+const SPEED_BOOST_FACTOR: f32 = 1.6;
+const MAGNET_RADIUS: f32 = 250.0;
+const MAGNET_SPEED: f32 = 350.0;
+const SLOW_TIME_FACTOR: f32 = 0.4;
+const BOMB_RADIUS: f32 = 250.0;
+
+// game modes
+const TIME_ATTACK_DURATION: f32 = 60.0;
+const TARGET_SCORE_STARS: u32 = 25;
+const ZEN_DURATION: f32 = 180.0;
+const HARDCORE_LIVES: u32 = 1;
+const HARDCORE_ENEMY_SPEED_FACTOR: f32 = 1.5;
+const HIGH_SCORES_PER_MODE: usize = 10;
+
+// population caps
+const MAX_STARS: usize = 20;
+const MAX_ENEMIES: usize = 24;
+const MAX_POWER_UPS: usize = 3;
+const MAX_PROJECTILES: usize = 120;
+
+// assets
+const ASSETS_FOLDER: &str = "assets";
+const ASSET_MANIFEST_PATH: &str = "game.assets.ron";
+const ENEMY_CATALOG_PATH: &str = "game.enemies.ron";
+const ACHIEVEMENT_CATALOG_PATH: &str = "game.achievements.ron";
+
+// level editor
+const EDITOR_GRID_SIZE: f32 = 32.0;
+// mouse wheel step of obstacle sizes
+const EDITOR_RESIZE_STEP: f32 = 8.0;
+
+// settings
+const INPUT_BINDINGS_PATH: &str = "settings/input.ron";
+const STICK_DEADZONE: f32 = 0.2;
+// a whole-stick deadzone would divide by zero when rescaling
+const MAX_STICK_DEADZONE: f32 = 0.9;
+const AUDIO_SETTINGS_PATH: &str = "settings/audio.ron";
+const ACHIEVEMENTS_PATH: &str = "settings/achievements.ron";
+const STATS_PATH: &str = "settings/stats.ron";
+const CAMERA_SETTINGS_PATH: &str = "settings/camera.ron";
+
+// achievements
+const ACHIEVEMENT_TOAST_TIME: f32 = 3.0;
+
+// camera
+// larger arenas scroll, the camera follows the players
+const MAX_WINDOW_WIDTH: f32 = 1280.0;
+const MAX_WINDOW_HEIGHT: f32 = 800.0;
+// rate of the exponential smoothing of the follow and the zoom, per second
+const CAMERA_FOLLOW_SPEED: f32 = 4.0;
+// space around the players when co-op zooms out to keep everyone in view
+const CAMERA_ZOOM_MARGIN: f32 = 160.0;
+// trauma from 0.0 to 1.0, the shake grows with its square
+const CAMERA_HIT_TRAUMA: f32 = 0.6;
+const CAMERA_EXPLOSION_TRAUMA: f32 = 0.3;
+const CAMERA_TRAUMA_DECAY: f32 = 1.2;
+const CAMERA_MAX_SHAKE_OFFSET: f32 = 24.0;
+const CAMERA_MAX_SHAKE_ANGLE: f32 = 0.05;
+
+// audio
+const MAX_SFX_VOICES: usize = 8;
+const MUSIC_CROSSFADE_TIME: f32 = 1.5;
+// the intense layer fades in from MUSIC_CALM_ENEMY_COUNT to MUSIC_INTENSE_ENEMY_COUNT enemies
+const MUSIC_CALM_ENEMY_COUNT: usize = 4;
+const MUSIC_INTENSE_ENEMY_COUNT: usize = 16;
+// pixels to spatial audio units, sounds get quieter past ~300 px from an ear
+const SPATIAL_AUDIO_SCALE: f32 = 1.0 / 300.0;
+const LISTENER_EAR_GAP: f32 = 400.0;
 
 // endregion:		--- Game constants
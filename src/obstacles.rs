@@ -0,0 +1,84 @@
+use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Collision shape of an obstacle, centered on its transform
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ObstacleShape {
+    /// Axis-aligned rectangle, walls are long thin ones
+    Rect {
+        half_width: f32,
+        half_height: f32,
+    },
+    Circle {
+        radius: f32,
+    },
+}
+
+/// How a circle overlaps an obstacle
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    /// Surface normal pointing out of the obstacle
+    pub normal: Vec2,
+    /// Distance to move along `normal` to stop overlapping
+    pub depth: f32,
+}
+
+impl ObstacleShape {
+    pub fn size(&self) -> Vec2 {
+        match self {
+            ObstacleShape::Rect {
+                half_width,
+                half_height,
+            } => Vec2::new(*half_width, *half_height) * 2.0,
+            ObstacleShape::Circle { radius } => Vec2::splat(*radius * 2.0),
+        }
+    }
+
+    /// Overlap of the circle at `point` with the obstacle at `center`, if any
+    pub fn contact(&self, center: Vec2, point: Vec2, radius: f32) -> Option<Contact> {
+        match self {
+            ObstacleShape::Circle {
+                radius: obstacle_radius,
+            } => {
+                let offset = point - center;
+                let distance = offset.length();
+                let depth = obstacle_radius + radius - distance;
+                (depth > 0.0).then(|| Contact {
+                    normal: offset.try_normalize().unwrap_or(Vec2::Y),
+                    depth,
+                })
+            }
+            ObstacleShape::Rect {
+                half_width,
+                half_height,
+            } => {
+                let half_size = Vec2::new(*half_width, *half_height);
+                let local = point - center;
+                let closest = local.clamp(-half_size, half_size);
+                let offset = local - closest;
+
+                if offset != Vec2::ZERO {
+                    let distance = offset.length();
+                    return (distance < radius).then(|| Contact {
+                        normal: offset / distance,
+                        depth: radius - distance,
+                    });
+                }
+
+                // center inside, leave through the closest face
+                let gap = half_size - local.abs();
+                if gap.x < gap.y {
+                    Some(Contact {
+                        normal: Vec2::new(local.x.signum(), 0.0),
+                        depth: gap.x + radius,
+                    })
+                } else {
+                    Some(Contact {
+                        normal: Vec2::new(0.0, local.y.signum()),
+                        depth: gap.y + radius,
+                    })
+                }
+            }
+        }
+    }
+}
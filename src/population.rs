@@ -0,0 +1,35 @@
+/// Kinds of entities under a population cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EntityKind {
+    Star,
+    /// Bosses are not counted
+    Enemy,
+    PowerUp,
+    Projectile,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 4] = [
+        EntityKind::Star,
+        EntityKind::Enemy,
+        EntityKind::PowerUp,
+        EntityKind::Projectile,
+    ];
+}
+
+/// What happens when a kind reaches its cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnPolicy {
+    /// The spawners stop until the count drops
+    Skip,
+    /// Spawns go on, the oldest entities are removed
+    DespawnOldest,
+    /// Spawns go on, the entities farthest from the players are removed
+    DespawnFarthest,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PopulationCap {
+    pub max: usize,
+    pub policy: SpawnPolicy,
+}
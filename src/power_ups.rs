@@ -0,0 +1,61 @@
+use bevy::render::color::Color;
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PowerUpKind {
+    /// Hits are absorbed
+    Shield,
+    SpeedBoost,
+    /// Nearby stars drift toward the player
+    Magnet,
+    /// Enemies and their projectiles slow down
+    SlowTime,
+    /// Clears the enemies around the player on pickup
+    Bomb,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::Shield,
+        PowerUpKind::SpeedBoost,
+        PowerUpKind::Magnet,
+        PowerUpKind::SlowTime,
+        PowerUpKind::Bomb,
+    ];
+
+    pub fn random() -> Self {
+        Self::ALL[rand::thread_rng().gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::SpeedBoost => "Speed",
+            PowerUpKind::Magnet => "Magnet",
+            PowerUpKind::SlowTime => "Slow time",
+            PowerUpKind::Bomb => "Bomb",
+        }
+    }
+
+    /// Tint of the pickup sprite
+    pub fn color(&self) -> Color {
+        match self {
+            PowerUpKind::Shield => Color::rgb(0.3, 0.9, 1.0),
+            PowerUpKind::SpeedBoost => Color::rgb(0.3, 1.0, 0.3),
+            PowerUpKind::Magnet => Color::rgb(1.0, 0.3, 0.8),
+            PowerUpKind::SlowTime => Color::rgb(0.6, 0.6, 1.0),
+            PowerUpKind::Bomb => Color::rgb(1.0, 0.4, 0.1),
+        }
+    }
+
+    /// Seconds the effect lasts, `None` for instant effects
+    pub fn duration(&self) -> Option<f32> {
+        match self {
+            PowerUpKind::Shield => Some(6.0),
+            PowerUpKind::SpeedBoost => Some(8.0),
+            PowerUpKind::Magnet => Some(10.0),
+            PowerUpKind::SlowTime => Some(5.0),
+            PowerUpKind::Bomb => None,
+        }
+    }
+}
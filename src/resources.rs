@@ -1,26 +1,80 @@
-use crate::{ENEMY_SPAWN_TIME, STAR_SPAWN_TIME};
+use crate::achievements::AchievementCatalog;
+use crate::assets::AssetManifest;
+use crate::audio::Sfx;
+use crate::components::PlayerId;
+use crate::editor::{EditorDrag, EditorTool};
+use crate::enemies::EnemyCatalog;
+use crate::events::DamageCause;
+use crate::game_modes::GameMode;
+use crate::input::{default_gamepad_bindings, Action, KeyboardLayout};
+use crate::levels::{Level, LevelItem};
+use crate::population::{EntityKind, PopulationCap, SpawnPolicy};
+use crate::settings::Persistent;
+use crate::{
+    Result, EDITOR_GRID_SIZE, ENEMY_SPAWN_TIME, HIGH_SCORES_PER_MODE, MAX_ENEMIES, MAX_POWER_UPS,
+    MAX_PROJECTILES, MAX_SFX_VOICES, MAX_STARS, MAX_STICK_DEADZONE, POWER_UP_SPAWN_TIME,
+    STAR_SPAWN_TIME, STICK_DEADZONE,
+};
+use bevy::asset::{AssetServer, Handle, UntypedHandle};
+use bevy::audio::AudioSource;
 use bevy::ecs::system::Resource;
+use bevy::input::gamepad::{Gamepad, GamepadButtonType};
+use bevy::input::keyboard::KeyCode;
+use bevy::math::Vec2;
+use bevy::render::texture::Image;
 use bevy::time::{Timer, TimerMode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct Score {
-    pub value: u32,
+    pub values: BTreeMap<PlayerId, u32>,
 }
 
-impl Default for Score {
-    fn default() -> Self {
-        Self { value: 0 }
+impl Score {
+    pub fn add(&mut self, player_id: PlayerId, amount: u32) {
+        *self.values.entry(player_id).or_default() += amount;
+    }
+
+    pub fn get(&self, player_id: PlayerId) -> u32 {
+        self.values.get(&player_id).copied().unwrap_or_default()
     }
+
+    pub fn total(&self) -> u32 {
+        self.values.values().sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    /// Length of the run in seconds
+    pub seconds: f32,
 }
 
-#[derive(Resource, Debug)]
+/// Leaderboard of every game mode, best runs first
+#[derive(Resource, Debug, Default)]
 pub struct HighScores {
-    pub scores: Vec<(String, u32)>,
+    pub scores: BTreeMap<GameMode, Vec<HighScore>>,
 }
 
-impl Default for HighScores {
-    fn default() -> Self {
-        Self { scores: Vec::new() }
+impl HighScores {
+    pub fn get(&self, mode: GameMode) -> &[HighScore] {
+        self.scores.get(&mode).map_or(&[], Vec::as_slice)
+    }
+
+    /// Rank `high_score` in the partition of `mode`, keeping the best `HIGH_SCORES_PER_MODE`
+    pub fn add(&mut self, mode: GameMode, high_score: HighScore) {
+        let scores = self.scores.entry(mode).or_default();
+        scores.push(high_score);
+        if mode.ranks_by_time() {
+            scores.sort_by(|a, b| a.seconds.total_cmp(&b.seconds));
+        } else {
+            scores.sort_by_key(|high_score| std::cmp::Reverse(high_score.score));
+        }
+        scores.truncate(HIGH_SCORES_PER_MODE);
     }
 }
 
@@ -37,6 +91,32 @@ impl Default for StarSpawnTimer {
     }
 }
 
+#[derive(Resource)]
+pub struct PowerUpSpawnTimer {
+    pub timer: Timer,
+}
+
+impl Default for PowerUpSpawnTimer {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(POWER_UP_SPAWN_TIME, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Speed factor of enemies and their projectiles, raised in hardcore mode,
+/// lowered by the slow-time power-up
+#[derive(Resource)]
+pub struct EnemyTimeScale {
+    pub value: f32,
+}
+
+impl Default for EnemyTimeScale {
+    fn default() -> Self {
+        Self { value: 1.0 }
+    }
+}
+
 #[derive(Resource)]
 pub struct EnemySpawnTimer {
     pub timer: Timer,
@@ -49,3 +129,602 @@ impl Default for EnemySpawnTimer {
         }
     }
 }
+
+#[derive(Resource, Default)]
+pub struct QuitConfirmation {
+    pub pending: bool,
+}
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct InputBindings {
+    pub layout: KeyboardLayout,
+    #[serde(default)]
+    pub bindings: BTreeMap<PlayerId, BTreeMap<Action, Vec<KeyCode>>>,
+    /// Shared by every gamepad, whichever player it is assigned to
+    #[serde(default = "default_gamepad_bindings")]
+    pub gamepad_bindings: BTreeMap<Action, Vec<GamepadButtonType>>,
+    #[serde(default = "default_stick_deadzone")]
+    pub stick_deadzone: f32,
+}
+
+fn default_stick_deadzone() -> f32 {
+    STICK_DEADZONE
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self::from_layout(KeyboardLayout::default())
+    }
+}
+
+impl InputBindings {
+    pub fn from_layout(layout: KeyboardLayout) -> Self {
+        Self {
+            layout,
+            bindings: PlayerId::ALL
+                .into_iter()
+                .map(|player_id| (player_id, layout.default_bindings(player_id)))
+                .collect(),
+            gamepad_bindings: default_gamepad_bindings(),
+            stick_deadzone: STICK_DEADZONE,
+        }
+    }
+
+    pub fn keys(&self, player_id: PlayerId, action: Action) -> &[KeyCode] {
+        self.bindings
+            .get(&player_id)
+            .and_then(|bindings| bindings.get(&action))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.gamepad_bindings
+            .get(&action)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// First key of the first player for `action`, shown in menu hints
+    pub fn key_hint(&self, action: Action) -> String {
+        self.keys(PlayerId::One, action)
+            .first()
+            .map_or("unbound".to_string(), |key| format!("{key:?}"))
+    }
+
+    pub fn rebind(&mut self, player_id: PlayerId, action: Action, keys: Vec<KeyCode>) {
+        self.bindings
+            .entry(player_id)
+            .or_default()
+            .insert(action, keys);
+    }
+}
+
+impl Persistent for InputBindings {
+    /// Actions missing from the file fall back to the layout profile,
+    /// the deadzone is kept below the whole stick
+    fn loaded(&mut self) {
+        self.stick_deadzone = self.stick_deadzone.clamp(0.0, MAX_STICK_DEADZONE);
+        for player_id in PlayerId::ALL {
+            let bindings = self.bindings.entry(player_id).or_default();
+            for (action, keys) in self.layout.default_bindings(player_id) {
+                bindings.entry(action).or_insert(keys);
+            }
+        }
+        for (action, buttons) in default_gamepad_bindings() {
+            self.gamepad_bindings.entry(action).or_insert(buttons);
+        }
+    }
+}
+
+/// Gamepad assigned to each player, follows connections and disconnections
+#[derive(Resource, Default)]
+pub struct PlayerGamepads {
+    pub gamepads: BTreeMap<PlayerId, Gamepad>,
+}
+
+/// Players taking part in the run, two of them for local co-op
+#[derive(Resource)]
+pub struct LocalPlayers {
+    pub players: Vec<PlayerId>,
+}
+
+impl Default for LocalPlayers {
+    fn default() -> Self {
+        Self {
+            players: vec![PlayerId::One],
+        }
+    }
+}
+
+impl LocalPlayers {
+    pub fn coop() -> Self {
+        Self {
+            players: PlayerId::ALL.to_vec(),
+        }
+    }
+}
+
+/// Players get a weapon and enemies can be shot down
+#[derive(Resource, Default)]
+pub struct ShooterMode {
+    pub enabled: bool,
+}
+
+/// Picking a level in the main menu opens it in the level editor instead of playing it
+#[derive(Resource, Default)]
+pub struct EditorMode {
+    pub enabled: bool,
+}
+
+/// Camera options, the screen shake can be turned off from the pause menu
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct CameraSettings {
+    pub screen_shake: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self { screen_shake: true }
+    }
+}
+
+impl Persistent for CameraSettings {}
+
+/// Volume buses (0.0 to 1.0) and voice limit, every sound effect goes through `PlaySfx`
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct AudioManager {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    /// Sound effects over this count are dropped
+    pub max_sfx_voices: usize,
+}
+
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 0.6,
+            max_sfx_voices: MAX_SFX_VOICES,
+        }
+    }
+}
+
+impl AudioManager {
+    pub fn effective_sfx_volume(&self) -> f32 {
+        (self.master_volume * self.sfx_volume).clamp(0.0, 1.0)
+    }
+
+    pub fn effective_music_volume(&self) -> f32 {
+        (self.master_volume * self.music_volume).clamp(0.0, 1.0)
+    }
+}
+
+impl Persistent for AudioManager {}
+
+/// Unlocked achievements and the counters of their goals, saved after every run
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    /// IDs in the achievement catalog
+    pub unlocked: BTreeSet<String>,
+    pub total_stars: u32,
+    pub total_bounces: u32,
+    pub runs: u32,
+    #[serde(skip)]
+    pub run: RunAchievementProgress,
+}
+
+impl Persistent for AchievementProgress {}
+
+/// Counters of the goals met within a single run, reset when it starts
+#[derive(Debug, Clone, Default)]
+pub struct RunAchievementProgress {
+    /// Mode of the run, the menus can switch the live one once it ended
+    pub game_mode: GameMode,
+    pub seconds: f32,
+    pub score: u32,
+    pub waves: u32,
+    /// A player got hurt
+    pub touched: bool,
+}
+
+/// Metrics of a run, carried by `GameOver` and added to the lifetime ones when it ends
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub stars_collected: u32,
+    /// Pixels moved by every player
+    pub distance: f32,
+    /// Seconds
+    pub time_survived: f32,
+    pub enemies_spawned: u32,
+    pub near_misses: u32,
+    /// Lives lost
+    pub deaths: BTreeMap<DamageCause, u32>,
+}
+
+impl RunStats {
+    pub fn record_death(&mut self, cause: DamageCause) {
+        *self.deaths.entry(cause).or_default() += 1;
+    }
+
+    pub fn total_deaths(&self) -> u32 {
+        self.deaths.values().sum()
+    }
+
+    pub fn add(&mut self, other: &RunStats) {
+        self.stars_collected += other.stars_collected;
+        self.distance += other.distance;
+        self.time_survived += other.time_survived;
+        self.enemies_spawned += other.enemies_spawned;
+        self.near_misses += other.near_misses;
+        for (cause, count) in other.deaths.iter() {
+            *self.deaths.entry(*cause).or_default() += count;
+        }
+    }
+}
+
+/// Lifetime metrics and those of the current run, saved whenever a run ends
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub runs: u32,
+    pub lifetime: RunStats,
+    /// Seconds of the longest run
+    pub longest_run: f32,
+    #[serde(skip)]
+    pub run: RunStats,
+}
+
+impl Stats {
+    /// Add the current run to the lifetime metrics, it stays readable until the next one
+    pub fn end_run(&mut self) {
+        self.runs += 1;
+        self.lifetime.add(&self.run);
+        self.longest_run = self.longest_run.max(self.run.time_survived);
+    }
+}
+
+impl Persistent for Stats {}
+
+/// Every sprite and sound of the game, loaded once during `AppState::Loading`
+#[derive(Resource)]
+pub struct GameAssets {
+    pub player_sprite: Handle<Image>,
+    pub star_sprite: Handle<Image>,
+    pub projectile_sprite: Handle<Image>,
+    pub power_up_sprite: Handle<Image>,
+    /// Sprite of every enemy type by name
+    pub enemy_sprites: HashMap<String, Handle<Image>>,
+    pub boss_sprites: HashMap<String, Handle<Image>>,
+    pub sounds: HashMap<Sfx, Vec<Handle<AudioSource>>>,
+    /// Tracks by manifest ID, states without one stay silent
+    pub music: HashMap<String, Handle<AudioSource>>,
+    pub levels: Vec<Handle<Level>>,
+}
+
+impl GameAssets {
+    /// Start loading every file of the manifest, failing on a missing logical ID
+    pub fn from_manifest(
+        asset_server: &AssetServer,
+        manifest: &AssetManifest,
+        enemy_catalog: &EnemyCatalog,
+    ) -> Result<Self> {
+        let sprite = |id: &str| -> Result<Handle<Image>> {
+            let path = manifest
+                .sprite(id)
+                .ok_or(format!("missing sprite `{id}` in the asset manifest"))?;
+            Ok(asset_server.load(path.to_string()))
+        };
+
+        let mut sounds = HashMap::new();
+        for sfx in Sfx::ALL {
+            let paths = manifest.sound(sfx.id()).ok_or(format!(
+                "missing sound `{}` in the asset manifest",
+                sfx.id()
+            ))?;
+            let handles = paths
+                .iter()
+                .map(|path| asset_server.load(path.clone()))
+                .collect();
+            sounds.insert(sfx, handles);
+        }
+
+        let music = manifest
+            .music
+            .iter()
+            .map(|(id, path)| (id.clone(), asset_server.load(path.clone())))
+            .collect();
+
+        if manifest.levels.is_empty() {
+            return Err("no level in the asset manifest".into());
+        }
+        let levels = manifest
+            .levels
+            .iter()
+            .map(|path| asset_server.load(path.clone()))
+            .collect();
+
+        let mut enemy_sprites = HashMap::new();
+        for (name, enemy_type) in enemy_catalog.types.iter() {
+            enemy_sprites.insert(name.clone(), sprite(&enemy_type.sprite)?);
+        }
+        let mut boss_sprites = HashMap::new();
+        for (name, boss_type) in enemy_catalog.bosses.iter() {
+            boss_sprites.insert(name.clone(), sprite(&boss_type.sprite)?);
+        }
+
+        Ok(Self {
+            player_sprite: sprite("player.sprite")?,
+            star_sprite: sprite("star.sprite")?,
+            projectile_sprite: sprite("projectile.sprite")?,
+            power_up_sprite: sprite("power_up.sprite")?,
+            enemy_sprites,
+            boss_sprites,
+            sounds,
+            music,
+            levels,
+        })
+    }
+
+    pub fn enemy_sprite(&self, name: &str) -> Handle<Image> {
+        self.enemy_sprites.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn boss_sprite(&self, name: &str) -> Handle<Image> {
+        self.boss_sprites.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Variants of a sound effect, one is picked at random on every play
+    pub fn sfx(&self, sfx: Sfx) -> &[Handle<AudioSource>] {
+        self.sounds.get(&sfx).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        let sprites = [
+            &self.player_sprite,
+            &self.star_sprite,
+            &self.projectile_sprite,
+            &self.power_up_sprite,
+        ]
+        .into_iter()
+        .chain(self.enemy_sprites.values())
+        .chain(self.boss_sprites.values())
+        .map(|handle| handle.clone().untyped());
+        let sounds = self
+            .sounds
+            .values()
+            .flatten()
+            .chain(self.music.values())
+            .map(|handle| handle.clone().untyped());
+        let levels = self.levels.iter().map(|handle| handle.clone().untyped());
+
+        sprites.chain(sounds).chain(levels).collect()
+    }
+}
+
+#[derive(Resource)]
+pub struct AssetManifestHandle {
+    pub handle: Handle<AssetManifest>,
+}
+
+#[derive(Resource)]
+pub struct EnemyCatalogHandle {
+    pub handle: Handle<EnemyCatalog>,
+}
+
+#[derive(Resource)]
+pub struct AchievementCatalogHandle {
+    pub handle: Handle<AchievementCatalog>,
+}
+
+#[derive(Resource, Default)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+/// Level highlighted in the main menu, index in `GameAssets::levels`
+#[derive(Resource, Default)]
+pub struct LevelSelection {
+    pub index: usize,
+}
+
+/// Script state of the level being played
+#[derive(Resource, Default)]
+pub struct LevelProgress {
+    /// Seconds since the level started, paused with the simulation
+    pub elapsed: f32,
+    /// Index of the next wave to release
+    pub next_wave: usize,
+    pub bosses_defeated: BTreeSet<String>,
+    /// Stars collected by the team
+    pub stars_collected: u32,
+    /// Won, through the level or the game mode
+    pub completed: bool,
+    /// Won on the star target of the game mode, the only finish ranked by time
+    pub target_reached: bool,
+    /// The time limit of the game mode ran out
+    pub time_up: bool,
+    /// The last life of the last player standing was lost
+    pub lost: bool,
+}
+
+impl LevelProgress {
+    /// `GameOver` was sent, nothing else may end the run
+    pub fn ended(&self) -> bool {
+        self.completed || self.time_up || self.lost
+    }
+}
+
+/// Layout being edited in `AppState::Editor`
+#[derive(Resource)]
+pub struct LevelEditor {
+    pub level: Level,
+    /// Asset the layout came from, refreshed on save so the game plays the new layout
+    pub handle: Handle<Level>,
+    /// Level file on disk, under the assets folder
+    pub path: PathBuf,
+    pub tool: EditorTool,
+    /// Type of the next enemy spawner
+    pub enemy_type: String,
+    pub snap: bool,
+    pub selected: Option<LevelItem>,
+    pub drag: Option<EditorDrag>,
+}
+
+impl LevelEditor {
+    /// Round `point` to the grid while snapping, always inside the arena
+    pub fn snap(&self, point: Vec2) -> Vec2 {
+        let point = if self.snap {
+            (point / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE
+        } else {
+            point
+        };
+        point.clamp(Vec2::ZERO, self.level.size())
+    }
+}
+
+/// Bosses already met in the current run, each one shows up once
+#[derive(Resource, Default)]
+pub struct BossEncounters {
+    pub encountered: BTreeSet<String>,
+}
+
+/// Population cap of every entity kind, kinds without one are unbounded
+#[derive(Resource, Debug, Clone)]
+pub struct PopulationCaps {
+    pub caps: BTreeMap<EntityKind, PopulationCap>,
+}
+
+impl Default for PopulationCaps {
+    fn default() -> Self {
+        Self {
+            caps: BTreeMap::from([
+                (
+                    EntityKind::Star,
+                    PopulationCap {
+                        max: MAX_STARS,
+                        policy: SpawnPolicy::DespawnOldest,
+                    },
+                ),
+                (
+                    EntityKind::Enemy,
+                    PopulationCap {
+                        max: MAX_ENEMIES,
+                        policy: SpawnPolicy::Skip,
+                    },
+                ),
+                (
+                    EntityKind::PowerUp,
+                    PopulationCap {
+                        max: MAX_POWER_UPS,
+                        policy: SpawnPolicy::Skip,
+                    },
+                ),
+                (
+                    EntityKind::Projectile,
+                    PopulationCap {
+                        max: MAX_PROJECTILES,
+                        policy: SpawnPolicy::DespawnOldest,
+                    },
+                ),
+            ]),
+        }
+    }
+}
+
+impl PopulationCaps {
+    pub fn get(&self, kind: EntityKind) -> Option<PopulationCap> {
+        self.caps.get(&kind).copied()
+    }
+
+    /// False when the kind is full and its policy skips spawns
+    pub fn allows_spawn(&self, kind: EntityKind, entity_budget: &EntityBudget) -> bool {
+        self.free_slots(kind, entity_budget) > 0
+    }
+
+    /// Spawns left before the kind is full, unbounded unless its policy skips spawns,
+    /// batches spawned in one frame take their share from it
+    pub fn free_slots(&self, kind: EntityKind, entity_budget: &EntityBudget) -> usize {
+        match self.get(kind) {
+            Some(PopulationCap {
+                max,
+                policy: SpawnPolicy::Skip,
+            }) => max.saturating_sub(entity_budget.count(kind)),
+            _ => usize::MAX,
+        }
+    }
+}
+
+/// Live entities of every capped kind, refreshed every frame
+#[derive(Resource, Default, Debug)]
+pub struct EntityBudget {
+    pub counts: BTreeMap<EntityKind, usize>,
+}
+
+impl EntityBudget {
+    pub fn count(&self, kind: EntityKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+/// From 0.0 (calm) to 1.0, grows with the number of enemies
+#[derive(Resource, Default)]
+pub struct MusicIntensity {
+    pub value: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(score: u32, seconds: f32) -> HighScore {
+        HighScore {
+            name: "Player".to_string(),
+            score,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn high_scores_rank_the_best_scores_first() {
+        let mut high_scores = HighScores::default();
+        for score in [5, 20, 10] {
+            high_scores.add(GameMode::Endless, high_score(score, 30.0));
+        }
+
+        let scores: Vec<u32> = high_scores
+            .get(GameMode::Endless)
+            .iter()
+            .map(|high_score| high_score.score)
+            .collect();
+        assert_eq!(scores, vec![20, 10, 5]);
+        assert!(high_scores.get(GameMode::Hardcore).is_empty());
+    }
+
+    #[test]
+    fn high_scores_rank_the_fastest_runs_first_when_timed() {
+        let mut high_scores = HighScores::default();
+        for seconds in [40.0, 25.0, 60.0] {
+            high_scores.add(GameMode::TargetScore, high_score(10, seconds));
+        }
+
+        let seconds: Vec<f32> = high_scores
+            .get(GameMode::TargetScore)
+            .iter()
+            .map(|high_score| high_score.seconds)
+            .collect();
+        assert_eq!(seconds, vec![25.0, 40.0, 60.0]);
+    }
+
+    #[test]
+    fn high_scores_keep_only_the_best_of_each_mode() {
+        let mut high_scores = HighScores::default();
+        for score in 0..HIGH_SCORES_PER_MODE as u32 + 5 {
+            high_scores.add(GameMode::Endless, high_score(score, 30.0));
+        }
+
+        let scores = high_scores.get(GameMode::Endless);
+        assert_eq!(scores.len(), HIGH_SCORES_PER_MODE);
+        assert_eq!(scores.last().unwrap().score, 5);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::Result;
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    Ok(ron::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Write `value` as pretty RON, creating the parent folders
+pub fn save_ron<T: Serialize>(value: &T, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        path,
+        ron::ser::to_string_pretty(value, PrettyConfig::default())?,
+    )?;
+
+    Ok(())
+}
+
+/// Resource kept in a RON file between sessions
+pub trait Persistent: Serialize + DeserializeOwned {
+    /// Fix up a value fresh from the file, nothing to do by default
+    fn loaded(&mut self) {}
+
+    fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut value: Self = load_ron(path)?;
+        value.loaded();
+        Ok(value)
+    }
+
+    fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_ron(self, path)
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::ecs::schedule::States;
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum AppState {
+    /// Every `GameAssets` handle is loading, gameplay waits for them
+    #[default]
+    Loading,
+    MainMenu,
+    Playing,
+    GameOver,
+    /// Every achievement and its progress, opened from the main menu
+    Achievements,
+    /// Lifetime player statistics, paged from the achievements screen
+    Stats,
+    /// Arena layout editing, only with `EditorMode`
+    Editor,
+}
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum SimulationState {
+    #[default]
+    Running,
+    Paused,
+}
@@ -0,0 +1,264 @@
+use bevy::ecs::component::Component;
+use bevy::math::{Rect, Vec2};
+use rand::Rng;
+use serde::Deserialize;
+
+/// Position and velocity of an agent or of a target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Kinematic {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum SteeringTarget {
+    ClosestPlayer,
+    Point(f32, f32),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum SteeringBehavior {
+    Seek(SteeringTarget),
+    /// Run away while the target is closer than `panic_radius`
+    Flee {
+        target: SteeringTarget,
+        panic_radius: f32,
+    },
+    /// Seek, slowing down inside `slowing_radius` to stop on the target
+    Arrive {
+        target: SteeringTarget,
+        slowing_radius: f32,
+    },
+    /// Seek where the target will be
+    Pursue(SteeringTarget),
+    /// Flee from where the target will be
+    Evade {
+        target: SteeringTarget,
+        panic_radius: f32,
+    },
+    /// Seek a point jittering on a circle `distance` ahead of the agent
+    Wander {
+        radius: f32,
+        distance: f32,
+        jitter: f32,
+    },
+    /// Push away from the neighbors closer than `radius`
+    Separation {
+        radius: f32,
+    },
+    /// Push away from the bounds closer than `margin`
+    WallAvoidance {
+        margin: f32,
+    },
+}
+
+impl SteeringBehavior {
+    /// Radius or margin the behavior scales with, it must be positive
+    pub fn radius(&self) -> Option<f32> {
+        match self {
+            SteeringBehavior::Seek(_) | SteeringBehavior::Pursue(_) => None,
+            SteeringBehavior::Flee { panic_radius, .. }
+            | SteeringBehavior::Evade { panic_radius, .. } => Some(*panic_radius),
+            SteeringBehavior::Arrive { slowing_radius, .. } => Some(*slowing_radius),
+            SteeringBehavior::Wander { radius, .. } | SteeringBehavior::Separation { radius } => {
+                Some(*radius)
+            }
+            SteeringBehavior::WallAvoidance { margin } => Some(*margin),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeightedBehavior {
+    pub behavior: SteeringBehavior,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// What an agent can see of the world while steering
+pub struct SteeringContext {
+    pub players: Vec<Kinematic>,
+    /// Positions of the agents of the same kind, the agent itself included
+    pub neighbors: Vec<Vec2>,
+    pub bounds: Rect,
+}
+
+impl SteeringContext {
+    fn resolve(&self, agent: Kinematic, target: SteeringTarget) -> Option<Kinematic> {
+        match target {
+            SteeringTarget::ClosestPlayer => self.players.iter().copied().min_by(|a, b| {
+                a.position
+                    .distance_squared(agent.position)
+                    .total_cmp(&b.position.distance_squared(agent.position))
+            }),
+            SteeringTarget::Point(x, y) => Some(Kinematic {
+                position: Vec2::new(x, y),
+                velocity: Vec2::ZERO,
+            }),
+        }
+    }
+}
+
+/// Weighted sum of behaviors, integrated within the agent speed and acceleration limits
+#[derive(Component, Debug, Clone)]
+pub struct Steering {
+    pub max_speed: f32,
+    pub max_acceleration: f32,
+    pub behaviors: Vec<WeightedBehavior>,
+    pub wander_angle: f32,
+}
+
+impl Steering {
+    pub fn new(max_speed: f32, max_acceleration: f32, behaviors: Vec<WeightedBehavior>) -> Self {
+        Self {
+            max_speed,
+            max_acceleration,
+            behaviors,
+            wander_angle: 0.0,
+        }
+    }
+
+    /// Velocity of `agent` after `delta` seconds of steering
+    pub fn steer(&mut self, agent: Kinematic, context: &SteeringContext, delta: f32) -> Vec2 {
+        let max_speed = self.max_speed;
+        let mut force = Vec2::ZERO;
+
+        for weighted in self.behaviors.iter() {
+            let behavior_force = match &weighted.behavior {
+                SteeringBehavior::Seek(target) => context
+                    .resolve(agent, *target)
+                    .map_or(Vec2::ZERO, |target| seek(agent, target.position, max_speed)),
+                SteeringBehavior::Flee {
+                    target,
+                    panic_radius,
+                } => context
+                    .resolve(agent, *target)
+                    .map_or(Vec2::ZERO, |target| {
+                        flee(agent, target.position, *panic_radius, max_speed)
+                    }),
+                SteeringBehavior::Arrive {
+                    target,
+                    slowing_radius,
+                } => context
+                    .resolve(agent, *target)
+                    .map_or(Vec2::ZERO, |target| {
+                        arrive(agent, target.position, *slowing_radius, max_speed)
+                    }),
+                SteeringBehavior::Pursue(target) => context
+                    .resolve(agent, *target)
+                    .map_or(Vec2::ZERO, |target| pursue(agent, target, max_speed)),
+                SteeringBehavior::Evade {
+                    target,
+                    panic_radius,
+                } => context
+                    .resolve(agent, *target)
+                    .map_or(Vec2::ZERO, |target| {
+                        evade(agent, target, *panic_radius, max_speed)
+                    }),
+                SteeringBehavior::Wander {
+                    radius,
+                    distance,
+                    jitter,
+                } => {
+                    self.wander_angle += rand::thread_rng().gen_range(-1.0..=1.0) * jitter * delta;
+                    wander(agent, self.wander_angle, *radius, *distance, max_speed)
+                }
+                SteeringBehavior::Separation { radius } => {
+                    separation(agent, &context.neighbors, *radius, max_speed)
+                }
+                SteeringBehavior::WallAvoidance { margin } => {
+                    wall_avoidance(agent, context.bounds, *margin, max_speed)
+                }
+            };
+            force += behavior_force * weighted.weight;
+        }
+
+        (agent.velocity + force.clamp_length_max(self.max_acceleration * delta))
+            .clamp_length_max(max_speed)
+    }
+}
+
+// region:			--- Behaviors
+
+// Each behavior returns the velocity change it asks for
+
+pub fn seek(agent: Kinematic, target: Vec2, max_speed: f32) -> Vec2 {
+    (target - agent.position).normalize_or_zero() * max_speed - agent.velocity
+}
+
+pub fn flee(agent: Kinematic, target: Vec2, panic_radius: f32, max_speed: f32) -> Vec2 {
+    if agent.position.distance(target) > panic_radius {
+        return Vec2::ZERO;
+    }
+
+    (agent.position - target).normalize_or_zero() * max_speed - agent.velocity
+}
+
+pub fn arrive(agent: Kinematic, target: Vec2, slowing_radius: f32, max_speed: f32) -> Vec2 {
+    let offset = target - agent.position;
+    let distance = offset.length();
+    let speed = max_speed * (distance / slowing_radius).min(1.0);
+
+    offset.normalize_or_zero() * speed - agent.velocity
+}
+
+/// Look ahead by the time needed to reach the target, capped to one second
+fn predict(agent: Kinematic, target: Kinematic, max_speed: f32) -> Vec2 {
+    let look_ahead = (agent.position.distance(target.position) / max_speed).min(1.0);
+    target.position + target.velocity * look_ahead
+}
+
+pub fn pursue(agent: Kinematic, target: Kinematic, max_speed: f32) -> Vec2 {
+    seek(agent, predict(agent, target, max_speed), max_speed)
+}
+
+pub fn evade(agent: Kinematic, target: Kinematic, panic_radius: f32, max_speed: f32) -> Vec2 {
+    flee(
+        agent,
+        predict(agent, target, max_speed),
+        panic_radius,
+        max_speed,
+    )
+}
+
+pub fn wander(agent: Kinematic, angle: f32, radius: f32, distance: f32, max_speed: f32) -> Vec2 {
+    let heading = agent.velocity.try_normalize().unwrap_or(Vec2::X);
+    let circle_center = agent.position + heading * distance;
+
+    seek(
+        agent,
+        circle_center + Vec2::from_angle(angle) * radius,
+        max_speed,
+    )
+}
+
+pub fn separation(agent: Kinematic, neighbors: &[Vec2], radius: f32, max_speed: f32) -> Vec2 {
+    let mut push = Vec2::ZERO;
+    for neighbor in neighbors {
+        let offset = agent.position - *neighbor;
+        let distance = offset.length();
+        // skip the agent itself
+        if distance > f32::EPSILON && distance < radius {
+            push += offset / distance * (1.0 - distance / radius);
+        }
+    }
+
+    push.clamp_length_max(1.0) * max_speed
+}
+
+pub fn wall_avoidance(agent: Kinematic, bounds: Rect, margin: f32, max_speed: f32) -> Vec2 {
+    let closeness = |distance: f32| (1.0 - distance / margin).max(0.0);
+    let position = agent.position;
+    let push = Vec2::new(
+        closeness(position.x - bounds.min.x) - closeness(bounds.max.x - position.x),
+        closeness(position.y - bounds.min.y) - closeness(bounds.max.y - position.y),
+    );
+
+    push * max_speed
+}
+
+// endregion:		--- Behaviors
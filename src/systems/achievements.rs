@@ -0,0 +1,143 @@
+use crate::achievements::AchievementCatalog;
+use crate::components::AchievementToast;
+use crate::events::{
+    AchievementUnlocked, EnemyBounced, GameOver, PlayerDamaged, ScoreGained, ScoreReason,
+    WaveReleased,
+};
+use crate::game_modes::GameMode;
+use crate::resources::{AchievementProgress, LevelProgress, RunAchievementProgress, Score};
+use crate::settings::Persistent;
+use crate::{ACHIEVEMENTS_PATH, ACHIEVEMENT_TOAST_TIME};
+use bevy::prelude::*;
+
+/// Height of a toast, newer ones stack above the older ones
+const TOAST_SPACING: f32 = 32.0;
+
+pub fn load_achievement_progress(mut commands: Commands) {
+    let achievement_progress = match AchievementProgress::load(ACHIEVEMENTS_PATH) {
+        Ok(achievement_progress) => achievement_progress,
+        Err(error) => {
+            println!("No achievement progress ({error})");
+            AchievementProgress::default()
+        }
+    };
+
+    commands.insert_resource(achievement_progress);
+}
+
+/// Counters move every frame, only save at the end of a run and on unlocks
+pub fn save_achievement_progress(achievement_progress: Res<AchievementProgress>) {
+    if let Err(error) = achievement_progress.save(ACHIEVEMENTS_PATH) {
+        println!("Failed to save achievement progress: {error}");
+    }
+}
+
+pub fn reset_run_achievements(
+    mut achievement_progress: ResMut<AchievementProgress>,
+    game_mode: Res<GameMode>,
+) {
+    achievement_progress.run = RunAchievementProgress {
+        game_mode: *game_mode,
+        ..Default::default()
+    };
+}
+
+/// Feed the gameplay events to the goal counters and unlock every goal reached,
+/// runs outside `AppState::Playing` too so the `GameOver` of the last frame counts,
+/// surviving and staying untouched mean nothing without enemies
+#[allow(clippy::too_many_arguments)]
+pub fn track_achievements(
+    mut score_gained_event_reader: EventReader<ScoreGained>,
+    mut enemy_bounced_event_reader: EventReader<EnemyBounced>,
+    mut wave_released_event_reader: EventReader<WaveReleased>,
+    mut player_damaged_event_reader: EventReader<PlayerDamaged>,
+    mut game_over_event_reader: EventReader<GameOver>,
+    mut achievement_unlocked_event_writer: EventWriter<AchievementUnlocked>,
+    achievement_catalog: Res<AchievementCatalog>,
+    level_progress: Res<LevelProgress>,
+    score: Res<Score>,
+    mut achievement_progress: ResMut<AchievementProgress>,
+) {
+    for event in score_gained_event_reader.read() {
+        if let ScoreReason::Star { .. } = event.reason {
+            achievement_progress.total_stars += 1;
+        }
+    }
+    achievement_progress.total_bounces += enemy_bounced_event_reader.read().count() as u32;
+    achievement_progress.run.waves += wave_released_event_reader.read().count() as u32;
+    if player_damaged_event_reader.read().count() > 0 {
+        achievement_progress.run.touched = true;
+    }
+    achievement_progress.runs += game_over_event_reader.read().count() as u32;
+    achievement_progress.run.seconds = level_progress.elapsed;
+    achievement_progress.run.score = score.total();
+
+    for achievement in achievement_catalog.achievements.iter() {
+        if achievement_progress.unlocked.contains(&achievement.id)
+            || !achievement.goal.reached(&achievement_progress)
+            || (!achievement_progress.run.game_mode.has_enemies()
+                && achievement.goal.needs_enemies())
+        {
+            continue;
+        }
+        println!("Achievement unlocked: {}", achievement.name);
+        achievement_progress.unlocked.insert(achievement.id.clone());
+        achievement_unlocked_event_writer.send(AchievementUnlocked {
+            id: achievement.id.clone(),
+        });
+    }
+}
+
+// region:			--- Toasts
+
+/// Bottom-right notice per unlock, above the ones still showing
+pub fn spawn_achievement_toasts(
+    mut commands: Commands,
+    mut achievement_unlocked_event_reader: EventReader<AchievementUnlocked>,
+    toast_query: Query<(), With<AchievementToast>>,
+    achievement_catalog: Res<AchievementCatalog>,
+) {
+    let mut toasts = toast_query.iter().len();
+
+    for event in achievement_unlocked_event_reader.read() {
+        let Some(achievement) = achievement_catalog.get(&event.id) else {
+            continue;
+        };
+
+        commands.spawn((
+            TextBundle::from_section(
+                format!("Achievement unlocked: {}", achievement.name),
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::GOLD,
+                    ..Default::default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(12.0),
+                bottom: Val::Px(12.0 + toasts as f32 * TOAST_SPACING),
+                ..Default::default()
+            }),
+            AchievementToast {
+                timer: Timer::from_seconds(ACHIEVEMENT_TOAST_TIME, TimerMode::Once),
+            },
+        ));
+        toasts += 1;
+    }
+}
+
+/// Real time, toasts keep going while the simulation is paused
+pub fn tick_achievement_toasts(
+    mut commands: Commands,
+    mut toast_query: Query<(Entity, &mut AchievementToast)>,
+    time: Res<Time<Real>>,
+) {
+    for (toast_entity, mut toast) in toast_query.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(toast_entity).despawn_recursive();
+        }
+    }
+}
+
+// endregion:		--- Toasts
@@ -0,0 +1,205 @@
+use crate::audio::MusicTrack;
+use crate::components::{Enemy, MusicVoice, SfxVoice};
+use crate::events::PlaySfx;
+use crate::levels::Level;
+use crate::resources::{AudioManager, GameAssets, MusicIntensity};
+use crate::settings::Persistent;
+use crate::states::AppState;
+use crate::{
+    AUDIO_SETTINGS_PATH, MUSIC_CALM_ENEMY_COUNT, MUSIC_CROSSFADE_TIME, MUSIC_INTENSE_ENEMY_COUNT,
+    SPATIAL_AUDIO_SCALE,
+};
+use bevy::{
+    audio::{SpatialScale, Volume},
+    prelude::*,
+};
+use rand::seq::SliceRandom;
+
+pub fn load_audio_manager(mut commands: Commands) {
+    let audio_manager = match AudioManager::load(AUDIO_SETTINGS_PATH) {
+        Ok(audio_manager) => audio_manager,
+        Err(error) => {
+            println!("Using default audio settings ({error})");
+            AudioManager::default()
+        }
+    };
+
+    commands.insert_resource(audio_manager);
+}
+
+pub fn save_audio_manager(audio_manager: Res<AudioManager>) {
+    if audio_manager.is_changed() && !audio_manager.is_added() {
+        if let Err(error) = audio_manager.save(AUDIO_SETTINGS_PATH) {
+            println!("Failed to save audio settings: {error}");
+        }
+    }
+}
+
+pub fn set_spatial_audio_scale(mut commands: Commands) {
+    commands.insert_resource(SpatialScale::new_2d(SPATIAL_AUDIO_SCALE));
+}
+
+/// Spawn a self-despawning voice per event, spatial when it has a position,
+/// dropping the ones over the voice limit
+pub fn play_sfx(
+    mut commands: Commands,
+    mut play_sfx_event_reader: EventReader<PlaySfx>,
+    voice_query: Query<(), With<SfxVoice>>,
+    game_assets: Res<GameAssets>,
+    audio_manager: Res<AudioManager>,
+) {
+    let mut voices = voice_query.iter().len();
+
+    for event in play_sfx_event_reader.read() {
+        if voices >= audio_manager.max_sfx_voices {
+            continue;
+        }
+        let Some(source) = game_assets.sfx(event.sfx).choose(&mut rand::thread_rng()) else {
+            continue;
+        };
+
+        let settings = PlaybackSettings::DESPAWN
+            .with_volume(Volume::new_absolute(audio_manager.effective_sfx_volume()));
+        let audio_bundle = AudioBundle {
+            source: source.clone(),
+            settings,
+        };
+
+        // positioned voices are heard through the camera `SpatialListener`
+        match event.position {
+            Some(position) => {
+                commands.spawn((
+                    AudioBundle {
+                        settings: settings.with_spatial(true),
+                        ..audio_bundle
+                    },
+                    TransformBundle::from_transform(Transform::from_translation(
+                        position.extend(0.0),
+                    )),
+                    SfxVoice {},
+                ));
+            }
+            None => {
+                commands.spawn((audio_bundle, SfxVoice {}));
+            }
+        }
+        voices += 1;
+    }
+}
+
+/// Apply bus changes to the sound effects already playing
+pub fn update_sfx_volume(
+    audio_manager: Res<AudioManager>,
+    voice_query: Query<&AudioSink, With<SfxVoice>>,
+    spatial_voice_query: Query<&SpatialAudioSink, With<SfxVoice>>,
+) {
+    if audio_manager.is_changed() {
+        for sink in voice_query.iter() {
+            sink.set_volume(audio_manager.effective_sfx_volume());
+        }
+        for sink in spatial_voice_query.iter() {
+            sink.set_volume(audio_manager.effective_sfx_volume());
+        }
+    }
+}
+
+// region:			--- Music
+
+/// Fade out the playing music and fade in the layers of the new app state track,
+/// a level with its own music replaces the base layer of the playing track,
+/// states sharing a track keep it playing
+pub fn play_state_music(
+    mut commands: Commands,
+    app_state: Res<State<AppState>>,
+    game_assets: Res<GameAssets>,
+    level: Option<Res<Level>>,
+    mut music_query: Query<&mut MusicVoice>,
+    mut current_track: Local<Option<MusicTrack>>,
+) {
+    if !app_state.is_changed() {
+        return;
+    }
+    let next_track = MusicTrack::from_state(*app_state.get());
+    if next_track.is_some() && next_track == *current_track {
+        return;
+    }
+    *current_track = next_track;
+
+    for mut music_voice in music_query.iter_mut() {
+        music_voice.fading_out = true;
+    }
+    let Some(track) = next_track else {
+        return;
+    };
+
+    let level_music = level
+        .and_then(|level| level.music.clone())
+        .filter(|_| track == MusicTrack::Playing);
+    let base_id = level_music.as_deref().unwrap_or(track.id());
+    let layers = [(base_id, false)]
+        .into_iter()
+        .chain(track.intense_id().map(|id| (id, true)));
+    for (id, intense) in layers {
+        if let Some(source) = game_assets.music.get(id) {
+            commands.spawn((
+                AudioBundle {
+                    source: source.clone(),
+                    settings: PlaybackSettings::LOOP.with_volume(Volume::new_absolute(0.0)),
+                },
+                MusicVoice {
+                    intense,
+                    gain: 0.0,
+                    fading_out: false,
+                },
+            ));
+        }
+    }
+}
+
+pub fn update_music_intensity(
+    enemy_query: Query<(), With<Enemy>>,
+    mut music_intensity: ResMut<MusicIntensity>,
+) {
+    let extra_enemies = enemy_query
+        .iter()
+        .len()
+        .saturating_sub(MUSIC_CALM_ENEMY_COUNT);
+    let max_extra_enemies = MUSIC_INTENSE_ENEMY_COUNT - MUSIC_CALM_ENEMY_COUNT;
+    music_intensity.value = (extra_enemies as f32 / max_extra_enemies as f32).min(1.0);
+}
+
+/// Step every fade, apply the music bus and intensity, despawn faded out layers
+pub fn crossfade_music(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    audio_manager: Res<AudioManager>,
+    music_intensity: Res<MusicIntensity>,
+    mut music_query: Query<(Entity, &mut MusicVoice, Option<&AudioSink>)>,
+) {
+    let step = time.delta_seconds() / MUSIC_CROSSFADE_TIME;
+
+    for (music_entity, mut music_voice, sink) in music_query.iter_mut() {
+        if music_voice.fading_out {
+            music_voice.gain -= step;
+            if music_voice.gain <= 0.0 {
+                commands.entity(music_entity).despawn();
+                continue;
+            }
+        } else {
+            music_voice.gain = (music_voice.gain + step).min(1.0);
+        }
+
+        if let Some(sink) = sink {
+            let layer_volume = if music_voice.intense {
+                music_intensity.value
+            } else {
+                1.0
+            };
+            sink.set_volume(
+                audio_manager.effective_music_volume() * music_voice.gain * layer_volume,
+            );
+        }
+    }
+}
+
+// endregion:		--- Music
@@ -0,0 +1,325 @@
+use super::closest_position;
+use super::spawns::{spawn_enemy, spawn_projectile};
+use crate::audio::Sfx;
+use crate::components::{Boss, BossHealthBar, BossHealthBarFill, Enemy, Player};
+use crate::enemies::{BossAttack, EnemyCatalog};
+use crate::events::{BossDefeated, BossSpawned, CameraShake, PlaySfx, ScoreGained, ScoreReason};
+use crate::game_modes::GameMode;
+use crate::levels::Level;
+use crate::population::EntityKind;
+use crate::resources::{
+    BossEncounters, EnemyTimeScale, EntityBudget, GameAssets, LevelProgress, PopulationCaps, Score,
+    ShooterMode,
+};
+use crate::CAMERA_HIT_TRAUMA;
+use bevy::prelude::*;
+use rand::random;
+use std::f32::consts::TAU;
+
+pub fn reset_boss_encounters(mut boss_encounters: ResMut<BossEncounters>) {
+    boss_encounters.encountered.clear();
+}
+
+/// Bring in the next boss once the team score or the waves released reach one of its triggers,
+/// one boss at a time and never in modes without enemies, bosses are not counted against the enemy cap
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_bosses(
+    mut commands: Commands,
+    mut boss_spawned_event_writer: EventWriter<BossSpawned>,
+    boss_query: Query<(), With<Boss>>,
+    level: Res<Level>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    score: Res<Score>,
+    level_progress: Res<LevelProgress>,
+    game_mode: Res<GameMode>,
+    mut boss_encounters: ResMut<BossEncounters>,
+) {
+    if !game_mode.has_enemies() || !boss_query.is_empty() {
+        return;
+    }
+    let waves = level_progress.next_wave as u32;
+    let Some(name) = enemy_catalog.next_boss(score.total(), waves, &boss_encounters.encountered)
+    else {
+        return;
+    };
+    let Some(boss_type) = enemy_catalog.boss(name) else {
+        return;
+    };
+    let arena = level.size();
+    let [r, g, b] = boss_type.tint;
+
+    // enter from the top, heading down
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_xyz(arena.x / 2.0, arena.y - boss_type.size / 2.0, 0.0),
+            texture: game_assets.boss_sprite(name),
+            sprite: Sprite {
+                color: Color::rgb(r, g, b),
+                custom_size: Some(Vec2::splat(boss_type.size)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Enemy {
+            direction: Vec2::new(random::<f32>() - 0.5, -1.0).normalize(),
+            speed: boss_type.speed,
+            size: boss_type.size,
+            health: boss_type.health,
+            points: 0,
+        },
+        Boss {
+            name: name.to_string(),
+            health: boss_type.health,
+            max_health: boss_type.health,
+            phase: 0,
+            next_attack: 0,
+            attack_timer: Timer::from_seconds(boss_type.phases[0].interval, TimerMode::Repeating),
+            charge_timer: None,
+            cruise_speed: boss_type.speed,
+        },
+    ));
+
+    println!("{name} appears!");
+    boss_encounters.encountered.insert(name.to_string());
+    boss_spawned_event_writer.send(BossSpawned {
+        name: name.to_string(),
+    });
+}
+
+/// Without shooting, every point scored while a boss is up hurts it
+pub fn damage_bosses(
+    mut boss_query: Query<&mut Boss>,
+    score: Res<Score>,
+    shooter_mode: Res<ShooterMode>,
+    mut last_score: Local<u32>,
+) {
+    // the score goes back to 0 on a new run
+    let gained = score.total().saturating_sub(*last_score);
+    *last_score = score.total();
+    if gained == 0 || shooter_mode.enabled {
+        return;
+    }
+
+    for mut boss in boss_query.iter_mut() {
+        boss.health = boss.health.saturating_sub(gained);
+    }
+}
+
+/// Switch phases with the boss health and run the attacks of the current phase in turn,
+/// minions over the enemy cap are left out
+#[allow(clippy::too_many_arguments)]
+pub fn boss_attacks(
+    mut commands: Commands,
+    mut boss_query: Query<(&Transform, &mut Enemy, &mut Boss)>,
+    player_query: Query<&Transform, With<Player>>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    population_caps: Res<PopulationCaps>,
+    entity_budget: Res<EntityBudget>,
+    enemy_time_scale: Res<EnemyTimeScale>,
+    time: Res<Time>,
+) {
+    // slow time stretches the attack rhythm and the charges too
+    let delta = time.delta().mul_f32(enemy_time_scale.value);
+    let mut free_slots = population_caps.free_slots(EntityKind::Enemy, &entity_budget);
+
+    for (transform, mut enemy, mut boss) in boss_query.iter_mut() {
+        let Some(boss_type) = enemy_catalog.boss(&boss.name) else {
+            continue;
+        };
+        let position = transform.translation.truncate();
+
+        let phase_index = boss_type.phase_index(boss.health_fraction());
+        let phase = &boss_type.phases[phase_index];
+        if phase_index != boss.phase {
+            println!("{} enters phase {}!", boss.name, phase_index + 1);
+            boss.phase = phase_index;
+            boss.next_attack = 0;
+            boss.attack_timer = Timer::from_seconds(phase.interval, TimerMode::Repeating);
+        }
+
+        if let Some(charge_timer) = boss.charge_timer.as_mut() {
+            if charge_timer.tick(delta).finished() {
+                enemy.speed = boss.cruise_speed;
+                boss.charge_timer = None;
+            }
+        }
+
+        if !boss.attack_timer.tick(delta).just_finished() || phase.attacks.is_empty() {
+            continue;
+        }
+        let attack = &phase.attacks[boss.next_attack % phase.attacks.len()];
+        boss.next_attack += 1;
+
+        match attack {
+            BossAttack::Ring { projectiles, speed } => {
+                for index in 0..*projectiles {
+                    let direction = Vec2::from_angle(TAU * index as f32 / *projectiles as f32);
+                    spawn_projectile(&mut commands, &game_assets, position, direction * *speed);
+                }
+            }
+            BossAttack::Charge { speed, duration } => {
+                let Some(target) = closest_position(
+                    position,
+                    player_query
+                        .iter()
+                        .map(|transform| transform.translation.truncate()),
+                ) else {
+                    continue;
+                };
+                if let Some(direction) = (target - position).try_normalize() {
+                    enemy.direction = direction;
+                }
+                enemy.speed = *speed;
+                boss.charge_timer = Some(Timer::from_seconds(*duration, TimerMode::Once));
+            }
+            BossAttack::SpawnMinions {
+                enemy: minion,
+                count,
+            } => {
+                let spawned = (*count).min(free_slots.try_into().unwrap_or(u32::MAX));
+                free_slots -= spawned as usize;
+                for index in 0..spawned {
+                    let direction = Vec2::from_angle(TAU * index as f32 / *count as f32);
+                    spawn_enemy(
+                        &mut commands,
+                        &game_assets,
+                        &enemy_catalog,
+                        minion,
+                        position + direction * enemy.size / 2.0,
+                        direction,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Remove bosses out of health and reward the surviving players
+#[allow(clippy::too_many_arguments)]
+pub fn defeat_bosses(
+    mut commands: Commands,
+    mut boss_defeated_event_writer: EventWriter<BossDefeated>,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut camera_shake_event_writer: EventWriter<CameraShake>,
+    boss_query: Query<(Entity, &Transform, &Boss)>,
+    player_query: Query<&Player>,
+    mut score_gained_event_writer: EventWriter<ScoreGained>,
+    enemy_catalog: Res<EnemyCatalog>,
+) {
+    for (boss_entity, transform, boss) in boss_query.iter() {
+        if boss.health > 0 {
+            continue;
+        }
+        let reward = enemy_catalog
+            .boss(&boss.name)
+            .map_or(0, |boss_type| boss_type.reward);
+
+        for player in player_query.iter() {
+            score_gained_event_writer.send(ScoreGained {
+                player_id: player.id,
+                amount: reward,
+                reason: ScoreReason::BossDefeated {
+                    name: boss.name.clone(),
+                },
+            });
+        }
+        println!("{} defeated! +{reward}", boss.name);
+        play_sfx_event_writer.send(PlaySfx {
+            sfx: Sfx::Explosion,
+            position: Some(transform.translation.truncate()),
+        });
+        camera_shake_event_writer.send(CameraShake {
+            trauma: CAMERA_HIT_TRAUMA,
+        });
+        boss_defeated_event_writer.send(BossDefeated {
+            name: boss.name.clone(),
+            reward,
+        });
+        commands.entity(boss_entity).despawn();
+    }
+}
+
+// region:			--- Health bar
+
+pub fn spawn_boss_health_bar(
+    mut commands: Commands,
+    mut boss_spawned_event_reader: EventReader<BossSpawned>,
+) {
+    for event in boss_spawned_event_reader.read() {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(16.0),
+                        left: Val::Percent(20.0),
+                        width: Val::Percent(60.0),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                BossHealthBar {},
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    event.name.to_uppercase(),
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ));
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(12.0),
+                            ..Default::default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Percent(100.0),
+                                    ..Default::default()
+                                },
+                                background_color: Color::rgb(0.9, 0.1, 0.1).into(),
+                                ..Default::default()
+                            },
+                            BossHealthBarFill {},
+                        ));
+                    });
+            });
+    }
+}
+
+pub fn update_boss_health_bar(
+    boss_query: Query<&Boss, Changed<Boss>>,
+    mut fill_query: Query<&mut Style, With<BossHealthBarFill>>,
+) {
+    for boss in boss_query.iter() {
+        for mut style in fill_query.iter_mut() {
+            style.width = Val::Percent(boss.health_fraction() * 100.0);
+        }
+    }
+}
+
+/// Run on `BossDefeated` and when leaving the game
+pub fn despawn_boss_health_bar(
+    mut commands: Commands,
+    health_bar_query: Query<Entity, With<BossHealthBar>>,
+) {
+    for health_bar_entity in health_bar_query.iter() {
+        commands.entity(health_bar_entity).despawn_recursive();
+    }
+}
+
+// endregion:		--- Health bar
@@ -0,0 +1,156 @@
+use crate::components::{CameraController, Player};
+use crate::events::CameraShake;
+use crate::levels::Level;
+use crate::resources::CameraSettings;
+use crate::settings::Persistent;
+use crate::{
+    CAMERA_FOLLOW_SPEED, CAMERA_MAX_SHAKE_ANGLE, CAMERA_MAX_SHAKE_OFFSET, CAMERA_SETTINGS_PATH,
+    CAMERA_TRAUMA_DECAY, CAMERA_ZOOM_MARGIN, LISTENER_EAR_GAP,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+use rand::random;
+
+pub fn spawn_camera(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
+    let window = window_query.get_single().unwrap();
+    let center = Vec2::new(window.width() / 2.0, window.height() / 2.0);
+
+    commands.spawn((
+        Camera2dBundle {
+            transform: Transform::from_translation(center.extend(0.0)),
+            ..Default::default()
+        },
+        CameraController::new(center),
+        // ears on the x axis, positional sound effects pan across the window
+        SpatialListener::new(LISTENER_EAR_GAP),
+    ));
+}
+
+pub fn load_camera_settings(mut commands: Commands) {
+    let camera_settings = match CameraSettings::load(CAMERA_SETTINGS_PATH) {
+        Ok(camera_settings) => camera_settings,
+        Err(error) => {
+            println!("Using default camera settings ({error})");
+            CameraSettings::default()
+        }
+    };
+
+    commands.insert_resource(camera_settings);
+}
+
+pub fn save_camera_settings(camera_settings: Res<CameraSettings>) {
+    if camera_settings.is_changed() && !camera_settings.is_added() {
+        if let Err(error) = camera_settings.save(CAMERA_SETTINGS_PATH) {
+            println!("Failed to save camera settings: {error}");
+        }
+    }
+}
+
+/// Center of the view on one axis, kept inside the arena, arenas narrower than the view stay centered
+fn view_center(center: f32, half_view: f32, arena: f32) -> f32 {
+    if arena <= half_view * 2.0 {
+        arena / 2.0
+    } else {
+        center.clamp(half_view, arena - half_view)
+    }
+}
+
+/// Keep every player in view: follow their center, and zoom out when they spread apart
+/// in an arena larger than the window
+pub fn follow_players(
+    mut camera_query: Query<&mut CameraController>,
+    player_query: Query<&Transform, With<Player>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    let window = window_query.get_single().unwrap();
+    let view = Vec2::new(window.width(), window.height());
+    let arena = level.size();
+
+    let positions = player_query
+        .iter()
+        .map(|transform| transform.translation.truncate());
+    let Some((min, max)) = positions.fold(None, |bounds: Option<(Vec2, Vec2)>, position| {
+        Some(bounds.map_or((position, position), |(min, max)| {
+            (min.min(position), max.max(position))
+        }))
+    }) else {
+        return;
+    };
+
+    // never zoom out past the whole arena, far enough for players in opposite corners
+    let max_zoom = (arena / view).max_element().max(1.0);
+    let target_zoom = ((max - min + CAMERA_ZOOM_MARGIN * 2.0) / view)
+        .max_element()
+        .clamp(1.0, max_zoom);
+    let half_view = view * target_zoom / 2.0;
+    let center = (min + max) / 2.0;
+    let target_focus = Vec2::new(
+        view_center(center.x, half_view.x, arena.x),
+        view_center(center.y, half_view.y, arena.y),
+    );
+
+    let smoothing = 1.0 - (-CAMERA_FOLLOW_SPEED * time.delta_seconds()).exp();
+    for mut camera_controller in camera_query.iter_mut() {
+        camera_controller.focus = camera_controller.focus.lerp(target_focus, smoothing);
+        camera_controller.zoom += (target_zoom - camera_controller.zoom) * smoothing;
+    }
+}
+
+pub fn add_camera_trauma(
+    mut camera_shake_event_reader: EventReader<CameraShake>,
+    mut camera_query: Query<&mut CameraController>,
+) {
+    for event in camera_shake_event_reader.read() {
+        for mut camera_controller in camera_query.iter_mut() {
+            camera_controller.add_trauma(event.trauma);
+        }
+    }
+}
+
+/// Place the camera on its focus, offset and rolled by the shake but kept inside the arena,
+/// and decay the trauma, in real time so a shake does not freeze on pause
+pub fn update_camera_transform(
+    mut camera_query: Query<(
+        &mut Transform,
+        &mut OrthographicProjection,
+        &mut CameraController,
+    )>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    level: Option<Res<Level>>,
+    camera_settings: Res<CameraSettings>,
+    time: Res<Time<Real>>,
+) {
+    let window = window_query.get_single().unwrap();
+    let view = Vec2::new(window.width(), window.height());
+
+    for (mut transform, mut projection, mut camera_controller) in camera_query.iter_mut() {
+        let shake = if camera_settings.screen_shake {
+            camera_controller.shake()
+        } else {
+            0.0
+        };
+        let offset = Vec2::new(random::<f32>() * 2.0 - 1.0, random::<f32>() * 2.0 - 1.0)
+            * CAMERA_MAX_SHAKE_OFFSET
+            * shake;
+        let angle = (random::<f32>() * 2.0 - 1.0) * CAMERA_MAX_SHAKE_ANGLE * shake;
+
+        let mut center = camera_controller.focus + offset;
+        // no arena to stay in before the first level is picked
+        if let Some(level) = level.as_ref() {
+            let arena = level.size();
+            let half_view = view * camera_controller.zoom / 2.0;
+            center = Vec2::new(
+                view_center(center.x, half_view.x, arena.x),
+                view_center(center.y, half_view.y, arena.y),
+            );
+        }
+
+        transform.translation = center.extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(angle);
+        projection.scale = camera_controller.zoom;
+
+        camera_controller.trauma =
+            (camera_controller.trauma - CAMERA_TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+    }
+}
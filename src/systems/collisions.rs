@@ -1,34 +1,150 @@
-use crate::components::{Enemy, Player, Star};
-use crate::events::GameOver;
-use crate::resources::Score;
-use crate::{ENEMY_SIZE, ENEMY_SPEED, PLAYER_SIZE, PLAYER_SPEED, STAR_SIZE};
+use crate::audio::Sfx;
+use crate::components::{
+    ActivePowerUps, Boss, Combo, Enemy, EnemyProjectile, Health, Invulnerable, NearMissTracker,
+    Player, PlayerProjectile, Star,
+};
+use crate::events::{
+    CameraShake, DamageCause, NearMiss, PlaySfx, PlayerDamaged, ScoreGained, ScoreReason,
+};
+use crate::power_ups::PowerUpKind;
+use crate::{
+    CAMERA_EXPLOSION_TRAUMA, CAMERA_HIT_TRAUMA, INVULNERABILITY_BLINK_TIME, INVULNERABILITY_TIME,
+    NEAR_MISS_MARGIN, PLAYER_SIZE, PLAYER_SPEED, PROJECTILE_SIZE, STAR_SIZE,
+};
 use bevy::{prelude::*, window::PrimaryWindow};
 use rand::{Rng, RngCore};
+use std::collections::HashMap;
 
+type HittablePlayer<'a> = (
+    Entity,
+    &'a Player,
+    &'a Transform,
+    &'a mut Health,
+    &'a ActivePowerUps,
+    &'a mut NearMissTracker,
+);
+
+/// Something a player can run into
+struct Hazard {
+    translation: Vec3,
+    radius: f32,
+    cause: DamageCause,
+    /// Enemies can be dodged for a near miss
+    enemy: Option<Entity>,
+    /// Projectiles are consumed by the hit
+    projectile: Option<Entity>,
+}
+
+/// Hits cost one health point and grant a short invulnerability, lives are handled on `PlayerDamaged`,
+/// enemies leaving the `NEAR_MISS_MARGIN` of a player without touching it are near misses
+#[allow(clippy::too_many_arguments)]
 pub fn enemy_hit_player(
     mut commands: Commands,
-    mut game_over_event_writer: EventWriter<GameOver>,
-    mut player_query: Query<(Entity, &Transform), With<Player>>,
-    mut enemy_query: Query<&Transform, With<Enemy>>,
-    asset_server: Res<AssetServer>,
-    score: Res<Score>,
+    mut player_damaged_event_writer: EventWriter<PlayerDamaged>,
+    mut near_miss_event_writer: EventWriter<NearMiss>,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut camera_shake_event_writer: EventWriter<CameraShake>,
+    mut player_query: Query<HittablePlayer, Without<Invulnerable>>,
+    enemy_query: Query<(Entity, &Transform, &Enemy, Option<&Boss>)>,
+    projectile_query: Query<(Entity, &Transform), With<EnemyProjectile>>,
 ) {
-    if let Ok((player_entity, player_transform)) = player_query.get_single_mut() {
-        for enemy_transform in enemy_query.iter() {
-            let distance = player_transform
-                .translation
-                .distance(enemy_transform.translation);
-            let player_radius = PLAYER_SIZE / 2.0;
-            let enemy_radius = ENEMY_SIZE / 2.0;
-
-            if distance < player_radius + enemy_radius {
-                println!("Enemy hit player! GAME OVER!");
-                commands.spawn(AudioBundle {
-                    source: asset_server.load("audio/explosionCrunch_000.ogg"),
-                    ..Default::default()
+    let hazards = enemy_query
+        .iter()
+        .map(|(entity, transform, enemy, boss)| Hazard {
+            translation: transform.translation,
+            radius: enemy.size / 2.0,
+            cause: if boss.is_some() {
+                DamageCause::Boss
+            } else {
+                DamageCause::Enemy
+            },
+            enemy: Some(entity),
+            projectile: None,
+        })
+        .chain(projectile_query.iter().map(|(entity, transform)| Hazard {
+            translation: transform.translation,
+            radius: PROJECTILE_SIZE / 2.0,
+            cause: DamageCause::Projectile,
+            enemy: None,
+            projectile: Some(entity),
+        }))
+        .collect::<Vec<_>>();
+
+    for (
+        player_entity,
+        player,
+        player_transform,
+        mut health,
+        active_power_ups,
+        mut near_miss_tracker,
+    ) in player_query.iter_mut()
+    {
+        let shielded = active_power_ups.is_active(PowerUpKind::Shield);
+        let player_radius = PLAYER_SIZE / 2.0;
+        // close enemies of this frame, true when touching
+        let mut close_enemies = HashMap::new();
+        let mut hit = false;
+
+        for hazard in hazards.iter() {
+            let distance = player_transform.translation.distance(hazard.translation);
+            let contact_distance = player_radius + hazard.radius;
+
+            if distance >= contact_distance {
+                if let Some(enemy) = hazard.enemy {
+                    if distance < contact_distance + NEAR_MISS_MARGIN {
+                        close_enemies.insert(enemy, false);
+                    }
+                }
+                continue;
+            }
+
+            if let Some(enemy) = hazard.enemy {
+                close_enemies.insert(enemy, true);
+            }
+            if let Some(projectile_entity) = hazard.projectile {
+                commands.entity(projectile_entity).despawn();
+            }
+            // the shield absorbs the hit, enemies go through it harmlessly
+            if shielded {
+                continue;
+            }
+            println!("Enemy hit {}!", player.id.name());
+            play_sfx_event_writer.send(PlaySfx {
+                sfx: Sfx::Explosion,
+                position: Some(player_transform.translation.truncate()),
+            });
+            camera_shake_event_writer.send(CameraShake {
+                trauma: CAMERA_HIT_TRAUMA,
+            });
+            health.current = health.current.saturating_sub(1);
+            commands.entity(player_entity).insert(Invulnerable {
+                timer: Timer::from_seconds(INVULNERABILITY_TIME, TimerMode::Once),
+                blink_timer: Timer::from_seconds(INVULNERABILITY_BLINK_TIME, TimerMode::Repeating),
+            });
+            player_damaged_event_writer.send(PlayerDamaged {
+                player: player_entity,
+                player_id: player.id,
+                cause: hazard.cause,
+                health: health.current,
+                position: player_transform.translation.truncate(),
+            });
+            hit = true;
+            break;
+        }
+
+        // nothing dodged on a hit, invulnerability pauses the tracking
+        if hit {
+            near_miss_tracker.close_enemies.clear();
+            continue;
+        }
+        for enemy in near_miss_tracker.update(close_enemies) {
+            // destroyed enemies did not pass by
+            if enemy_query.contains(enemy) {
+                near_miss_event_writer.send(NearMiss {
+                    player: player_entity,
+                    player_id: player.id,
+                    position: player_transform.translation.truncate(),
                 });
-                commands.entity(player_entity).despawn();
-                game_over_event_writer.send(GameOver { score: score.value });
             }
         }
     }
@@ -36,26 +152,89 @@ pub fn enemy_hit_player(
 
 pub fn player_hit_star(
     mut commands: Commands,
-    mut player_query: Query<&Transform, With<Player>>,
-    mut star_query: Query<(Entity, &Transform), With<Star>>,
-    asset_server: Res<AssetServer>,
-    mut score: ResMut<Score>,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut score_gained_event_writer: EventWriter<ScoreGained>,
+    mut player_query: Query<(&Player, &Transform, &mut Combo)>,
+    star_query: Query<(Entity, &Transform, &Star)>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        for (star_entity, star_transform) in star_query.iter() {
+    for (star_entity, star_transform, star) in star_query.iter() {
+        // a star goes to the first player touching it
+        let collector = player_query.iter_mut().find(|(_, player_transform, _)| {
             let distance = player_transform
                 .translation
                 .distance(star_transform.translation);
+            distance < PLAYER_SIZE / 2.0 + STAR_SIZE / 2.0
+        });
 
-            if distance < PLAYER_SIZE / 2.0 + STAR_SIZE / 2.0 {
-                println!("Player hit star!");
-                score.value += 1;
-                commands.spawn(AudioBundle {
-                    source: asset_server.load("audio/glass_002.ogg"),
-                    ..Default::default()
-                });
-                commands.entity(star_entity).despawn();
-            }
+        if let Some((player, _, mut combo)) = collector {
+            println!("{} hit star!", player.id.name());
+            combo.hit();
+            let multiplier = combo.multiplier();
+            score_gained_event_writer.send(ScoreGained {
+                player_id: player.id,
+                amount: star.kind.value() * multiplier,
+                reason: ScoreReason::Star {
+                    kind: star.kind,
+                    multiplier,
+                },
+            });
+            play_sfx_event_writer.send(PlaySfx {
+                sfx: Sfx::StarPickup,
+                position: Some(star_transform.translation.truncate()),
+            });
+            commands.entity(star_entity).despawn();
+        }
+    }
+}
+
+/// Shots damage the first enemy they touch, destroyed enemies score for the shooter
+pub fn player_projectile_hit_enemy(
+    mut commands: Commands,
+    mut score_gained_event_writer: EventWriter<ScoreGained>,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut camera_shake_event_writer: EventWriter<CameraShake>,
+    projectile_query: Query<(Entity, &Transform, &PlayerProjectile)>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Enemy, Option<&mut Boss>)>,
+) {
+    for (projectile_entity, projectile_transform, projectile) in projectile_query.iter() {
+        let target = enemy_query
+            .iter_mut()
+            .find(|(_, enemy_transform, enemy, _)| {
+                // destroyed earlier this frame, the despawn is still pending
+                if enemy.health == 0 {
+                    return false;
+                }
+                let distance = enemy_transform
+                    .translation
+                    .distance(projectile_transform.translation);
+                distance < enemy.size / 2.0 + PROJECTILE_SIZE / 2.0
+            });
+        let Some((enemy_entity, enemy_transform, mut enemy, boss)) = target else {
+            continue;
+        };
+        commands.entity(projectile_entity).despawn();
+
+        // bosses are removed by `defeat_bosses`
+        if let Some(mut boss) = boss {
+            boss.health = boss.health.saturating_sub(1);
+            continue;
+        }
+
+        enemy.health = enemy.health.saturating_sub(1);
+        if enemy.health == 0 {
+            play_sfx_event_writer.send(PlaySfx {
+                sfx: Sfx::Explosion,
+                position: Some(enemy_transform.translation.truncate()),
+            });
+            camera_shake_event_writer.send(CameraShake {
+                trauma: CAMERA_EXPLOSION_TRAUMA,
+            });
+            score_gained_event_writer.send(ScoreGained {
+                player_id: projectile.owner,
+                amount: enemy.points,
+                reason: ScoreReason::EnemyDestroyed,
+            });
+            commands.entity(enemy_entity).despawn();
         }
     }
 }
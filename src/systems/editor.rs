@@ -0,0 +1,433 @@
+use super::cursor_world_position;
+use super::levels::fit_arena;
+use crate::components::{CameraController, LevelEditorHud};
+use crate::editor::{EditorDrag, EditorTool};
+use crate::enemies::EnemyCatalog;
+use crate::input::Action;
+use crate::levels::{EnemyPlacement, Level, LevelItem, ObstaclePlacement};
+use crate::obstacles::ObstacleShape;
+use crate::resources::{GameAssets, LevelEditor, LevelSelection};
+use crate::settings::{load_ron, save_ron};
+use crate::states::AppState;
+use crate::{ASSETS_FOLDER, EDITOR_GRID_SIZE, EDITOR_RESIZE_STEP, STAR_SIZE};
+use bevy::{input::mouse::MouseWheel, prelude::*, window::PrimaryWindow};
+use std::f32::consts::FRAC_PI_4;
+use std::path::Path;
+
+const GRID_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.06);
+const OBSTACLE_COLOR: Color = Color::rgb(0.55, 0.55, 0.7);
+const STAR_COLOR: Color = Color::GOLD;
+const SELECTED_COLOR: Color = Color::CYAN;
+
+// region:			--- Editor
+
+/// Edit the level picked in the main menu, in a window fitted to its arena
+#[allow(clippy::too_many_arguments)]
+pub fn open_level_editor(
+    mut commands: Commands,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut CameraController>,
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    level_selection: Res<LevelSelection>,
+    level: Res<Level>,
+) {
+    let handle = game_assets.levels[level_selection.index].clone();
+    let Some(asset_path) = asset_server.get_path(handle.id()) else {
+        println!("Unknown file for level {}", level.name);
+        return;
+    };
+    let path = Path::new(ASSETS_FOLDER).join(asset_path.path());
+    println!("Editing {} ({})", level.name, path.display());
+
+    fit_arena(&mut window_query, &mut camera_query, level.size());
+    commands.insert_resource(LevelEditor {
+        level: level.clone(),
+        handle,
+        path,
+        tool: EditorTool::default(),
+        enemy_type: enemy_catalog
+            .types
+            .keys()
+            .next()
+            .cloned()
+            .unwrap_or_default(),
+        snap: true,
+        selected: None,
+        drag: None,
+    });
+}
+
+pub fn close_level_editor(mut commands: Commands) {
+    commands.remove_resource::<LevelEditor>();
+}
+
+/// Pause goes back to the main menu, Confirm play-tests the layout as it is
+pub fn level_editor_exit(
+    mut commands: Commands,
+    action_input: Res<Input<Action>>,
+    level_editor: Res<LevelEditor>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Pause) {
+        next_app_state.set(AppState::MainMenu);
+    } else if action_input.just_pressed(Action::Confirm) {
+        commands.insert_resource(level_editor.level.clone());
+        next_app_state.set(AppState::Playing);
+    }
+}
+
+// endregion:		--- Editor
+
+// region:			--- Editing
+
+/// Add a placement of the current tool at `point`
+fn place_item(level_editor: &mut LevelEditor, point: Vec2) -> LevelItem {
+    let position = point.to_array();
+    let level = &mut level_editor.level;
+
+    if let Some(shape) = level_editor.tool.obstacle_shape() {
+        level.obstacles.push(ObstaclePlacement { position, shape });
+        return LevelItem::Obstacle(level.obstacles.len() - 1);
+    }
+    match level_editor.tool {
+        EditorTool::Enemy => {
+            level.enemies.push(EnemyPlacement {
+                enemy: level_editor.enemy_type.clone(),
+                position,
+                direction: None,
+            });
+            LevelItem::Enemy(level.enemies.len() - 1)
+        }
+        _ => {
+            level.stars.push(position);
+            LevelItem::Star(level.stars.len() - 1)
+        }
+    }
+}
+
+/// Point an enemy spawner toward `cursor`, on one of eight headings while snapping
+fn aim_enemy(level_editor: &mut LevelEditor, index: usize, cursor: Vec2) {
+    let snap = level_editor.snap;
+    let Some(enemy) = level_editor.level.enemies.get_mut(index) else {
+        return;
+    };
+    let Some(direction) = (cursor - Vec2::from(enemy.position)).try_normalize() else {
+        return;
+    };
+
+    let direction = if snap {
+        let angle = direction.y.atan2(direction.x);
+        Vec2::from_angle((angle / FRAC_PI_4).round() * FRAC_PI_4)
+    } else {
+        direction
+    };
+    enemy.direction = Some(direction.to_array());
+}
+
+/// Left click grabs a placement or places a new one, Shift+drag aims an enemy, right click deletes
+pub fn level_editor_mouse_input(
+    mouse_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    enemy_catalog: Res<EnemyCatalog>,
+    mut level_editor: ResMut<LevelEditor>,
+) {
+    if mouse_input.just_released(MouseButton::Left) {
+        level_editor.drag = None;
+    }
+    let Some(cursor) = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| cursor_world_position(window, &camera_query))
+    else {
+        return;
+    };
+    let point = level_editor.snap(cursor);
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let aim = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let (item, drag) = match level_editor.level.item_at(cursor, &enemy_catalog) {
+            Some(item @ LevelItem::Enemy(_)) if aim => (item, EditorDrag::Aim),
+            Some(item) => (item, EditorDrag::Move),
+            // a new enemy spawner is aimed by dragging right away
+            None => match place_item(&mut level_editor, point) {
+                item @ LevelItem::Enemy(_) => (item, EditorDrag::Aim),
+                item => (item, EditorDrag::Move),
+            },
+        };
+        level_editor.selected = Some(item);
+        level_editor.drag = Some(drag);
+    } else if mouse_input.pressed(MouseButton::Left) {
+        match (level_editor.selected, level_editor.drag) {
+            (Some(item), Some(EditorDrag::Move)) => {
+                level_editor.level.set_item_position(item, point);
+            }
+            (Some(LevelItem::Enemy(index)), Some(EditorDrag::Aim)) => {
+                aim_enemy(&mut level_editor, index, cursor);
+            }
+            _ => {}
+        }
+    }
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some(item) = level_editor.level.item_at(cursor, &enemy_catalog) {
+            level_editor.level.remove_item(item);
+            level_editor.selected = None;
+            level_editor.drag = None;
+        }
+    }
+}
+
+/// Tools on the number keys, enemy type, snapping and resizing shortcuts
+pub fn level_editor_keyboard_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_event_reader: EventReader<MouseWheel>,
+    enemy_catalog: Res<EnemyCatalog>,
+    mut level_editor: ResMut<LevelEditor>,
+) {
+    let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+    for (key, tool) in keys.into_iter().zip(EditorTool::ALL) {
+        if keyboard_input.just_pressed(key) {
+            level_editor.tool = tool;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::G) {
+        level_editor.snap = !level_editor.snap;
+    }
+
+    // next catalog type, the selected spawner follows
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        let names = enemy_catalog.types.keys().collect::<Vec<_>>();
+        let next = names
+            .iter()
+            .position(|name| **name == level_editor.enemy_type)
+            .map_or(0, |index| (index + 1) % names.len());
+        if let Some(name) = names.get(next) {
+            level_editor.enemy_type = (*name).clone();
+        }
+        if let Some(LevelItem::Enemy(index)) = level_editor.selected {
+            let enemy_type = level_editor.enemy_type.clone();
+            if let Some(enemy) = level_editor.level.enemies.get_mut(index) {
+                enemy.enemy = enemy_type;
+            }
+        }
+    }
+
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        if let Some(item) = level_editor.selected.take() {
+            level_editor.level.remove_item(item);
+            level_editor.drag = None;
+        }
+    }
+
+    let Some(LevelItem::Obstacle(index)) = level_editor.selected else {
+        mouse_wheel_event_reader.clear();
+        return;
+    };
+    let Some(obstacle) = level_editor.level.obstacles.get_mut(index) else {
+        return;
+    };
+
+    // turn walls between horizontal and vertical
+    if keyboard_input.just_pressed(KeyCode::R) {
+        if let ObstacleShape::Rect {
+            half_width,
+            half_height,
+        } = &mut obstacle.shape
+        {
+            std::mem::swap(half_width, half_height);
+        }
+    }
+
+    // the wheel resizes, the height of walls with Shift
+    let scroll: f32 = mouse_wheel_event_reader
+        .read()
+        .map(|event| event.y.signum())
+        .sum();
+    if scroll != 0.0 {
+        let tall = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let length = match &mut obstacle.shape {
+            ObstacleShape::Rect { half_height, .. } if tall => half_height,
+            ObstacleShape::Rect { half_width, .. } => half_width,
+            ObstacleShape::Circle { radius } => radius,
+        };
+        *length = (*length + scroll * EDITOR_RESIZE_STEP).max(EDITOR_RESIZE_STEP);
+    }
+}
+
+/// Ctrl+S writes the layout to its level file, Ctrl+L reloads it from there
+pub fn level_editor_file_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut CameraController>,
+    enemy_catalog: Res<EnemyCatalog>,
+    mut levels: ResMut<Assets<Level>>,
+    mut level_editor: ResMut<LevelEditor>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::S) {
+        if let Err(error) = level_editor.level.validate(&enemy_catalog) {
+            println!("Not saved, invalid level: {error}");
+            return;
+        }
+        match save_ron(&level_editor.level, &level_editor.path) {
+            Ok(()) => {
+                println!("Saved {}", level_editor.path.display());
+                // the level select and the next run pick up the new layout
+                levels.insert(level_editor.handle.id(), level_editor.level.clone());
+            }
+            Err(error) => println!("Failed to save {}: {error}", level_editor.path.display()),
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::L) {
+        match load_ron::<Level>(&level_editor.path) {
+            Ok(level) => {
+                println!("Loaded {}", level_editor.path.display());
+                fit_arena(&mut window_query, &mut camera_query, level.size());
+                level_editor.level = level;
+                level_editor.selected = None;
+                level_editor.drag = None;
+            }
+            Err(error) => println!("Failed to load {}: {error}", level_editor.path.display()),
+        }
+    }
+}
+
+// endregion:		--- Editing
+
+// region:			--- Drawing
+
+/// Grid, arena bounds and every placement, the selected one highlighted
+pub fn draw_level_editor(
+    mut gizmos: Gizmos,
+    enemy_catalog: Res<EnemyCatalog>,
+    level_editor: Res<LevelEditor>,
+) {
+    let level = &level_editor.level;
+    let size = level.size();
+    let item_color = |item: LevelItem, color: Color| {
+        if level_editor.selected == Some(item) {
+            SELECTED_COLOR
+        } else {
+            color
+        }
+    };
+
+    if level_editor.snap {
+        for index in 0..=(size.x / EDITOR_GRID_SIZE) as usize {
+            let x = index as f32 * EDITOR_GRID_SIZE;
+            gizmos.line_2d(Vec2::new(x, 0.0), Vec2::new(x, size.y), GRID_COLOR);
+        }
+        for index in 0..=(size.y / EDITOR_GRID_SIZE) as usize {
+            let y = index as f32 * EDITOR_GRID_SIZE;
+            gizmos.line_2d(Vec2::new(0.0, y), Vec2::new(size.x, y), GRID_COLOR);
+        }
+    }
+    gizmos.rect_2d(size / 2.0, 0.0, size, Color::WHITE);
+
+    for (index, obstacle) in level.obstacles.iter().enumerate() {
+        let position = Vec2::from(obstacle.position);
+        let color = item_color(LevelItem::Obstacle(index), OBSTACLE_COLOR);
+        match obstacle.shape {
+            ObstacleShape::Rect { .. } => {
+                gizmos.rect_2d(position, 0.0, obstacle.shape.size(), color);
+            }
+            ObstacleShape::Circle { radius } => {
+                gizmos.circle_2d(position, radius, color);
+            }
+        }
+    }
+
+    for (index, enemy) in level.enemies.iter().enumerate() {
+        let Some(enemy_type) = enemy_catalog.get(&enemy.enemy) else {
+            continue;
+        };
+        let position = Vec2::from(enemy.position);
+        let radius = enemy_type.size / 2.0;
+        let [r, g, b] = enemy_type.tint;
+        let color = item_color(LevelItem::Enemy(index), Color::rgb(r, g, b));
+
+        gizmos.circle_2d(position, radius, color);
+        // no line for a random heading
+        if let Some(direction) = enemy.direction {
+            gizmos.line_2d(
+                position,
+                position + Vec2::from(direction) * radius * 2.0,
+                color,
+            );
+        }
+    }
+
+    for (index, star) in level.stars.iter().enumerate() {
+        let color = item_color(LevelItem::Star(index), STAR_COLOR);
+        gizmos.circle_2d(Vec2::from(*star), STAR_SIZE / 2.0, color);
+    }
+}
+
+pub fn spawn_level_editor_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..Default::default()
+        }),
+        LevelEditorHud {},
+    ));
+}
+
+pub fn update_level_editor_hud(
+    level_editor: Res<LevelEditor>,
+    mut hud_query: Query<&mut Text, With<LevelEditorHud>>,
+) {
+    if !level_editor.is_changed() {
+        return;
+    }
+    let level = &level_editor.level;
+    let value = format!(
+        "{} - {}\n\
+        Tool: {} (1-4) | Enemy: {} (Tab) | Snap: {} (G)\n\
+        {} obstacles, {} enemy spawners, {} star spawners\n\
+        Left: place, move | Shift+drag: aim | Right, Del: delete | Wheel, R: resize, rotate\n\
+        Ctrl+S: save | Ctrl+L: load | Enter: play-test | Esc: main menu",
+        level.name,
+        level_editor.path.display(),
+        level_editor.tool.name(),
+        level_editor.enemy_type,
+        if level_editor.snap { "on" } else { "off" },
+        level.obstacles.len(),
+        level.enemies.len(),
+        level.stars.len(),
+    );
+
+    for mut text in hud_query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+pub fn despawn_level_editor_hud(
+    mut commands: Commands,
+    hud_query: Query<Entity, With<LevelEditorHud>>,
+) {
+    for hud_entity in hud_query.iter() {
+        commands.entity(hud_entity).despawn_recursive();
+    }
+}
+
+// endregion:		--- Drawing
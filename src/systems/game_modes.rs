@@ -0,0 +1,111 @@
+use crate::components::{GameModeHud, Player};
+use crate::events::{GameOver, ScoreGained, ScoreReason};
+use crate::game_modes::GameMode;
+use crate::resources::{LevelProgress, LocalPlayers, Score, Stats};
+use bevy::prelude::*;
+
+/// Count the stars collected, end the run on the star target or the time limit of the mode
+#[allow(clippy::too_many_arguments)]
+pub fn apply_game_mode_rules(
+    mut score_gained_event_reader: EventReader<ScoreGained>,
+    mut game_over_event_writer: EventWriter<GameOver>,
+    player_query: Query<(), With<Player>>,
+    game_mode: Res<GameMode>,
+    mut level_progress: ResMut<LevelProgress>,
+    score: Res<Score>,
+    local_players: Res<LocalPlayers>,
+    stats: Res<Stats>,
+) {
+    let stars = score_gained_event_reader
+        .read()
+        .filter(|event| matches!(event.reason, ScoreReason::Star { .. }))
+        .count();
+    level_progress.stars_collected += stars as u32;
+
+    // a lost run is not won
+    if level_progress.ended() || player_query.is_empty() {
+        return;
+    }
+
+    if game_mode
+        .target_stars()
+        .is_some_and(|target| level_progress.stars_collected >= target)
+    {
+        println!(
+            "{} stars in {:.1}s!",
+            level_progress.stars_collected, level_progress.elapsed
+        );
+        level_progress.completed = true;
+        level_progress.target_reached = true;
+    } else if game_mode
+        .time_limit()
+        .is_some_and(|time_limit| level_progress.elapsed >= time_limit)
+    {
+        println!("Time up!");
+        level_progress.time_up = true;
+    } else {
+        return;
+    }
+
+    game_over_event_writer.send(GameOver::new(&score, &local_players, &stats));
+}
+
+// region:			--- HUD
+
+pub fn spawn_game_mode_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..Default::default()
+        }),
+        GameModeHud {},
+    ));
+}
+
+/// Mode name, with the time left or the star count for timed modes
+pub fn update_game_mode_hud(
+    game_mode: Res<GameMode>,
+    level_progress: Res<LevelProgress>,
+    mut hud_query: Query<&mut Text, With<GameModeHud>>,
+) {
+    let value = match (game_mode.time_limit(), game_mode.target_stars()) {
+        (Some(time_limit), _) => format!(
+            "{} - {:.0}s left - {} stars",
+            game_mode.name(),
+            (time_limit - level_progress.elapsed).max(0.0).ceil(),
+            level_progress.stars_collected
+        ),
+        (_, Some(target)) => format!(
+            "{} - {}/{} stars - {:.1}s",
+            game_mode.name(),
+            level_progress.stars_collected,
+            target,
+            level_progress.elapsed
+        ),
+        _ => game_mode.name().to_string(),
+    };
+
+    for mut text in hud_query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn despawn_game_mode_hud(mut commands: Commands, hud_query: Query<Entity, With<GameModeHud>>) {
+    for hud_entity in hud_query.iter() {
+        commands.entity(hud_entity).despawn_recursive();
+    }
+}
+
+// endregion:		--- HUD
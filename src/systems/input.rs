@@ -0,0 +1,239 @@
+use super::cursor_world_position;
+use crate::components::{Aim, MovementAxis, Player, PlayerId};
+use crate::input::{apply_radial_deadzone, Action};
+use crate::resources::{InputBindings, LocalPlayers, PlayerGamepads};
+use crate::settings::Persistent;
+use crate::INPUT_BINDINGS_PATH;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+pub fn load_input_bindings(mut commands: Commands) {
+    let input_bindings = match InputBindings::load(INPUT_BINDINGS_PATH) {
+        Ok(input_bindings) => input_bindings,
+        Err(error) => {
+            println!("Using default input bindings ({error})");
+            InputBindings::default()
+        }
+    };
+
+    commands.insert_resource(input_bindings);
+}
+
+/// Persist the bindings every time they are edited, not when they were just loaded
+pub fn save_input_bindings(input_bindings: Res<InputBindings>) {
+    if input_bindings.is_changed() && !input_bindings.is_added() {
+        if let Err(error) = input_bindings.save(INPUT_BINDINGS_PATH) {
+            println!("Failed to save input bindings: {error}");
+        }
+    }
+}
+
+/// Give each local player a gamepad while enough are plugged in, reassigning on hot-plug
+pub fn handle_gamepad_connections(
+    mut gamepad_connection_event_reader: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    local_players: Res<LocalPlayers>,
+    mut player_gamepads: ResMut<PlayerGamepads>,
+) {
+    for event in gamepad_connection_event_reader.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => println!("Gamepad connected: {}", info.name),
+            GamepadConnection::Disconnected => {
+                println!("Gamepad disconnected");
+                player_gamepads
+                    .gamepads
+                    .retain(|_, gamepad| *gamepad != event.gamepad);
+            }
+        }
+
+        for player_id in local_players.players.iter() {
+            if player_gamepads.gamepads.contains_key(player_id) {
+                continue;
+            }
+            let free_gamepad = gamepads.iter().find(|gamepad| {
+                !player_gamepads
+                    .gamepads
+                    .values()
+                    .any(|assigned| assigned == gamepad)
+            });
+            if let Some(gamepad) = free_gamepad {
+                player_gamepads.gamepads.insert(*player_id, gamepad);
+            }
+        }
+    }
+}
+
+fn action_pressed(
+    player_id: PlayerId,
+    action: Action,
+    keyboard_input: &Input<KeyCode>,
+    gamepad_input: &Input<GamepadButton>,
+    input_bindings: &InputBindings,
+    player_gamepads: &PlayerGamepads,
+) -> bool {
+    let key_pressed =
+        keyboard_input.any_pressed(input_bindings.keys(player_id, action).iter().copied());
+    let button_pressed = player_gamepads
+        .gamepads
+        .get(&player_id)
+        .is_some_and(|gamepad| {
+            gamepad_input.any_pressed(
+                input_bindings
+                    .buttons(action)
+                    .iter()
+                    .map(|button_type| GamepadButton::new(*gamepad, *button_type)),
+            )
+        });
+
+    key_pressed || button_pressed
+}
+
+/// Translate the raw keyboard and gamepad state into actions of any local player,
+/// must run after `InputSystem`
+pub fn update_action_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    input_bindings: Res<InputBindings>,
+    player_gamepads: Res<PlayerGamepads>,
+    local_players: Res<LocalPlayers>,
+    mut action_input: ResMut<Input<Action>>,
+) {
+    action_input.clear();
+
+    for action in Action::ALL {
+        let pressed = local_players.players.iter().any(|player_id| {
+            action_pressed(
+                *player_id,
+                action,
+                &keyboard_input,
+                &gamepad_input,
+                &input_bindings,
+                &player_gamepads,
+            )
+        });
+
+        if pressed {
+            action_input.press(action);
+        } else {
+            action_input.release(action);
+        }
+    }
+}
+
+/// Twin-stick aiming with the right stick, the first player may also aim with the mouse,
+/// keyboard-only players shoot where they move
+#[allow(clippy::too_many_arguments)]
+pub fn update_aim(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    input_bindings: Res<InputBindings>,
+    player_gamepads: Res<PlayerGamepads>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut player_query: Query<(&Player, &Transform, &MovementAxis, &mut Aim)>,
+) {
+    let cursor = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| cursor_world_position(window, &camera_query));
+
+    for (player, transform, movement_axis, mut aim) in player_query.iter_mut() {
+        let fire_pressed = action_pressed(
+            player.id,
+            Action::Fire,
+            &keyboard_input,
+            &gamepad_input,
+            &input_bindings,
+            &player_gamepads,
+        );
+
+        let stick = player_gamepads
+            .gamepads
+            .get(&player.id)
+            .map_or(Vec2::ZERO, |gamepad| {
+                let x = gamepad_axes
+                    .get(GamepadAxis::new(*gamepad, GamepadAxisType::RightStickX))
+                    .unwrap_or(0.0);
+                let y = gamepad_axes
+                    .get(GamepadAxis::new(*gamepad, GamepadAxisType::RightStickY))
+                    .unwrap_or(0.0);
+                apply_radial_deadzone(Vec2::new(x, y), input_bindings.stick_deadzone)
+            });
+
+        if let Some(direction) = stick.try_normalize() {
+            aim.direction = direction;
+            aim.firing = true;
+            continue;
+        }
+
+        match cursor.filter(|_| player.id == PlayerId::One) {
+            Some(cursor) => {
+                if let Some(direction) = (cursor - transform.translation.truncate()).try_normalize()
+                {
+                    aim.direction = direction;
+                }
+                aim.firing = fire_pressed || mouse_input.pressed(MouseButton::Left);
+            }
+            None => {
+                if let Some(direction) = movement_axis.value.try_normalize() {
+                    aim.direction = direction;
+                }
+                aim.firing = fire_pressed;
+            }
+        }
+    }
+}
+
+/// Digital moves (keys, D-pad) are normalized, the left stick keeps its analog magnitude
+pub fn update_movement_axis(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    input_bindings: Res<InputBindings>,
+    player_gamepads: Res<PlayerGamepads>,
+    mut player_query: Query<(&Player, &mut MovementAxis)>,
+) {
+    for (player, mut movement_axis) in player_query.iter_mut() {
+        let pressed = |action| {
+            action_pressed(
+                player.id,
+                action,
+                &keyboard_input,
+                &gamepad_input,
+                &input_bindings,
+                &player_gamepads,
+            )
+        };
+
+        let mut digital = Vec2::ZERO;
+        if pressed(Action::MoveLeft) {
+            digital.x -= 1.0;
+        }
+        if pressed(Action::MoveRight) {
+            digital.x += 1.0;
+        }
+        if pressed(Action::MoveUp) {
+            digital.y += 1.0;
+        }
+        if pressed(Action::MoveDown) {
+            digital.y -= 1.0;
+        }
+
+        let analog = player_gamepads
+            .gamepads
+            .get(&player.id)
+            .map_or(Vec2::ZERO, |gamepad| {
+                let x = gamepad_axes
+                    .get(GamepadAxis::new(*gamepad, GamepadAxisType::LeftStickX))
+                    .unwrap_or(0.0);
+                let y = gamepad_axes
+                    .get(GamepadAxis::new(*gamepad, GamepadAxisType::LeftStickY))
+                    .unwrap_or(0.0);
+                apply_radial_deadzone(Vec2::new(x, y), input_bindings.stick_deadzone)
+            });
+
+        movement_axis.value = (digital.normalize_or_zero() + analog).clamp_length_max(1.0);
+    }
+}
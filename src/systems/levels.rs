@@ -0,0 +1,202 @@
+use super::obstacles::spawn_obstacle;
+use super::random_free_position;
+use super::spawns::{spawn_enemy, spawn_star};
+use crate::components::{CameraController, Enemy, Obstacle, Player};
+use crate::enemies::EnemyCatalog;
+use crate::events::{BossDefeated, GameOver, WaveReleased};
+use crate::game_modes::GameMode;
+use crate::levels::{Level, WinCondition};
+use crate::population::EntityKind;
+use crate::resources::{
+    EntityBudget, GameAssets, LevelProgress, LocalPlayers, PopulationCaps, Score, Stats,
+};
+use crate::{MAX_WINDOW_HEIGHT, MAX_WINDOW_WIDTH};
+use bevy::{prelude::*, window::PrimaryWindow};
+use rand::random;
+use std::f32::consts::TAU;
+
+fn random_direction() -> Vec2 {
+    Vec2::from_angle(random::<f32>() * TAU)
+}
+
+/// Resize the window to the arena, up to `MAX_WINDOW_WIDTH` x `MAX_WINDOW_HEIGHT`,
+/// and center the camera zoomed out on all of it
+pub(crate) fn fit_arena(
+    window_query: &mut Query<&mut Window, With<PrimaryWindow>>,
+    camera_query: &mut Query<&mut CameraController>,
+    size: Vec2,
+) {
+    let view = size.min(Vec2::new(MAX_WINDOW_WIDTH, MAX_WINDOW_HEIGHT));
+    let mut window = window_query.get_single_mut().unwrap();
+    window.resolution.set(view.x, view.y);
+    for mut camera_controller in camera_query.iter_mut() {
+        camera_controller.focus = size / 2.0;
+        camera_controller.zoom = (size / view).max_element();
+        camera_controller.trauma = 0.0;
+    }
+}
+
+/// Fit the window to the arena and lay out its obstacles, enemies and stars
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_level(
+    mut commands: Commands,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut CameraController>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    level: Res<Level>,
+    game_mode: Res<GameMode>,
+) {
+    println!("Level: {} ({})", level.name, game_mode.name());
+    fit_arena(&mut window_query, &mut camera_query, level.size());
+
+    for obstacle in level.obstacles.iter() {
+        spawn_obstacle(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            Vec2::from(obstacle.position),
+            obstacle.shape,
+        );
+    }
+
+    let enemies = level.enemies.iter().filter(|_| game_mode.has_enemies());
+    for enemy in enemies {
+        let direction = enemy
+            .direction
+            .map(Vec2::from)
+            .and_then(Vec2::try_normalize)
+            .unwrap_or_else(random_direction);
+        spawn_enemy(
+            &mut commands,
+            &game_assets,
+            &enemy_catalog,
+            &enemy.enemy,
+            Vec2::from(enemy.position),
+            direction,
+        );
+    }
+
+    for star in level.stars.iter() {
+        spawn_star(&mut commands, &game_assets, Vec2::from(*star));
+    }
+}
+
+pub fn reset_level_progress(mut level_progress: ResMut<LevelProgress>) {
+    *level_progress = LevelProgress::default();
+}
+
+pub fn tick_level_clock(mut level_progress: ResMut<LevelProgress>, time: Res<Time>) {
+    level_progress.elapsed += time.delta_seconds();
+}
+
+/// Release the waves whose time came, at random spots of the arena clear of obstacles,
+/// waves still go by empty in modes without enemies and are cut short by the enemy cap
+#[allow(clippy::too_many_arguments)]
+pub fn release_waves(
+    mut commands: Commands,
+    mut wave_released_event_writer: EventWriter<WaveReleased>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    level: Res<Level>,
+    game_mode: Res<GameMode>,
+    population_caps: Res<PopulationCaps>,
+    entity_budget: Res<EntityBudget>,
+    mut level_progress: ResMut<LevelProgress>,
+) {
+    let arena = level.size();
+    let mut free_slots = population_caps.free_slots(EntityKind::Enemy, &entity_budget);
+
+    while let Some(wave) = level
+        .waves
+        .get(level_progress.next_wave)
+        .filter(|wave| wave.at <= level_progress.elapsed)
+    {
+        println!(
+            "Wave {}: {} x{}",
+            level_progress.next_wave + 1,
+            wave.enemy,
+            wave.count
+        );
+        let clearance = enemy_catalog
+            .get(&wave.enemy)
+            .map_or(0.0, |enemy_type| enemy_type.size / 2.0);
+        let count = if game_mode.has_enemies() {
+            wave.count.min(free_slots.try_into().unwrap_or(u32::MAX))
+        } else {
+            0
+        };
+        free_slots -= count as usize;
+
+        for _ in 0..count {
+            if let Some(position) = random_free_position(arena, &obstacle_query, clearance) {
+                spawn_enemy(
+                    &mut commands,
+                    &game_assets,
+                    &enemy_catalog,
+                    &wave.enemy,
+                    position,
+                    random_direction(),
+                );
+            }
+        }
+        if count > 0 {
+            wave_released_event_writer.send(WaveReleased {
+                index: level_progress.next_wave,
+                enemy: wave.enemy.clone(),
+                count,
+            });
+        }
+        level_progress.next_wave += 1;
+    }
+}
+
+/// End the run as a win once every win condition of the level holds
+#[allow(clippy::too_many_arguments)]
+pub fn check_level_completion(
+    mut boss_defeated_event_reader: EventReader<BossDefeated>,
+    mut game_over_event_writer: EventWriter<GameOver>,
+    player_query: Query<(), With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+    level: Res<Level>,
+    game_mode: Res<GameMode>,
+    mut level_progress: ResMut<LevelProgress>,
+    score: Res<Score>,
+    local_players: Res<LocalPlayers>,
+    stats: Res<Stats>,
+) {
+    for event in boss_defeated_event_reader.read() {
+        level_progress.bosses_defeated.insert(event.name.clone());
+    }
+
+    // levels without a condition are endless, a lost run is not won
+    if level_progress.ended() || level.win_conditions.is_empty() || player_query.is_empty() {
+        return;
+    }
+
+    let won = level
+        .win_conditions
+        .iter()
+        .all(|condition| match condition {
+            WinCondition::Score(target) => score.total() >= *target,
+            WinCondition::Survive(seconds) => level_progress.elapsed >= *seconds,
+            // without enemies these never hold, the time limit of the mode ends the run
+            WinCondition::ClearWaves => {
+                game_mode.has_enemies()
+                    && level_progress.next_wave >= level.waves.len()
+                    && enemy_query.is_empty()
+            }
+            WinCondition::DefeatBoss(name) => {
+                game_mode.has_enemies() && level_progress.bosses_defeated.contains(name)
+            }
+        });
+
+    if won {
+        println!("{} complete!", level.name);
+        level_progress.completed = true;
+        game_over_event_writer.send(GameOver::new(&score, &local_players, &stats));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::components::{Enemy, EnemyProjectile, Health, Invulnerable, Lives, Player};
+use crate::events::{GameOver, PlayerDamaged};
+use crate::levels::Level;
+use crate::resources::{LevelProgress, LocalPlayers, Score, Stats};
+use crate::PLAYER_SIZE;
+use bevy::prelude::*;
+use rand::random;
+
+/// Candidate spots tried when looking for a safe respawn
+const RESPAWN_CANDIDATES: usize = 16;
+
+/// Enemies and their projectiles
+type HazardFilter = (Or<(With<Enemy>, With<EnemyProjectile>)>, Without<Player>);
+
+/// Random spot of the arena the farthest from every hazard
+fn safe_spawn_position(arena: Vec2, hazards: &[Vec2]) -> Vec2 {
+    let margin = PLAYER_SIZE;
+    let candidates = (0..RESPAWN_CANDIDATES).map(|_| {
+        Vec2::new(
+            margin + random::<f32>() * (arena.x - margin * 2.0),
+            margin + random::<f32>() * (arena.y - margin * 2.0),
+        )
+    });
+    let clearance = |candidate: Vec2| {
+        hazards
+            .iter()
+            .map(|hazard| hazard.distance_squared(candidate))
+            .fold(f32::MAX, f32::min)
+    };
+
+    candidates
+        .max_by(|a, b| clearance(*a).total_cmp(&clearance(*b)))
+        .unwrap_or(Vec2::new(arena.x / 2.0, arena.y / 2.0))
+}
+
+/// Out of health players lose a life and respawn at a safe spot, the run ends with the last life,
+/// before the win checks so a run lost and won on the same frame ends once
+#[allow(clippy::too_many_arguments)]
+pub fn handle_player_damage(
+    mut commands: Commands,
+    mut player_damaged_event_reader: EventReader<PlayerDamaged>,
+    mut game_over_event_writer: EventWriter<GameOver>,
+    mut player_query: Query<(&mut Transform, &mut Health, &mut Lives), With<Player>>,
+    hazard_query: Query<&Transform, HazardFilter>,
+    level: Res<Level>,
+    score: Res<Score>,
+    local_players: Res<LocalPlayers>,
+    mut level_progress: ResMut<LevelProgress>,
+    mut stats: ResMut<Stats>,
+) {
+    let arena = level.size();
+    let mut players_alive = player_query.iter().len();
+    let mut players_lost = false;
+
+    for event in player_damaged_event_reader.read() {
+        if event.health > 0 {
+            continue;
+        }
+        let Ok((mut transform, mut health, mut lives)) = player_query.get_mut(event.player) else {
+            continue;
+        };
+
+        lives.remaining = lives.remaining.saturating_sub(1);
+        stats.run.record_death(event.cause);
+        if lives.remaining > 0 {
+            println!(
+                "{} lost a life, {} left!",
+                event.player_id.name(),
+                lives.remaining
+            );
+            let hazards = hazard_query
+                .iter()
+                .map(|transform| transform.translation.truncate())
+                .collect::<Vec<_>>();
+            let position = safe_spawn_position(arena, &hazards);
+            transform.translation = position.extend(transform.translation.z);
+            health.current = health.max;
+        } else {
+            println!("{} is out!", event.player_id.name());
+            commands.entity(event.player).despawn();
+            players_alive -= 1;
+            players_lost = true;
+        }
+    }
+
+    // the run only ends with the last life of the last player standing, unless already won
+    if players_lost && players_alive == 0 && !level_progress.ended() {
+        println!("GAME OVER!");
+        level_progress.lost = true;
+        game_over_event_writer.send(GameOver::new(&score, &local_players, &stats));
+    }
+}
+
+/// Blink invulnerable players until their invulnerability runs out
+pub fn blink_invulnerable_players(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Visibility, &mut Invulnerable), With<Player>>,
+    time: Res<Time>,
+) {
+    for (player_entity, mut visibility, mut invulnerable) in player_query.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(player_entity).remove::<Invulnerable>();
+            continue;
+        }
+
+        if invulnerable.blink_timer.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::achievements::AchievementCatalog;
+use crate::assets::AssetManifest;
+use crate::components::{LoadingScreen, LoadingText};
+use crate::enemies::EnemyCatalog;
+use crate::levels::Level;
+use crate::resources::{
+    AchievementCatalogHandle, AssetManifestHandle, EnemyCatalogHandle, GameAssets, LoadingProgress,
+};
+use crate::states::AppState;
+use crate::{ACHIEVEMENT_CATALOG_PATH, ASSET_MANIFEST_PATH, ENEMY_CATALOG_PATH};
+use bevy::{app::AppExit, asset::LoadState, prelude::*};
+
+pub fn load_asset_manifest(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AssetManifestHandle {
+        handle: asset_server.load(ASSET_MANIFEST_PATH),
+    });
+}
+
+pub fn load_enemy_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(EnemyCatalogHandle {
+        handle: asset_server.load(ENEMY_CATALOG_PATH),
+    });
+}
+
+pub fn load_achievement_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AchievementCatalogHandle {
+        handle: asset_server.load(ACHIEVEMENT_CATALOG_PATH),
+    });
+}
+
+/// Once the manifest and both catalogs are ready, start loading every file they use
+#[allow(clippy::too_many_arguments)]
+pub fn load_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    asset_manifest_handle: Res<AssetManifestHandle>,
+    asset_manifests: Res<Assets<AssetManifest>>,
+    enemy_catalog_handle: Res<EnemyCatalogHandle>,
+    enemy_catalogs: Res<Assets<EnemyCatalog>>,
+    achievement_catalog_handle: Res<AchievementCatalogHandle>,
+    achievement_catalogs: Res<Assets<AchievementCatalog>>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+) {
+    let data_files = [
+        (
+            asset_manifest_handle.handle.id().untyped(),
+            ASSET_MANIFEST_PATH,
+        ),
+        (
+            enemy_catalog_handle.handle.id().untyped(),
+            ENEMY_CATALOG_PATH,
+        ),
+        (
+            achievement_catalog_handle.handle.id().untyped(),
+            ACHIEVEMENT_CATALOG_PATH,
+        ),
+    ];
+    for (id, path) in data_files {
+        if asset_server.get_load_state(id) == Some(LoadState::Failed) {
+            println!("Failed to load the data file `{path}`");
+            app_exit_event_writer.send(AppExit);
+            return;
+        }
+    }
+    let (Some(asset_manifest), Some(enemy_catalog), Some(achievement_catalog)) = (
+        asset_manifests.get(&asset_manifest_handle.handle),
+        enemy_catalogs.get(&enemy_catalog_handle.handle),
+        achievement_catalogs.get(&achievement_catalog_handle.handle),
+    ) else {
+        return;
+    };
+
+    if let Err(error) = enemy_catalog.validate() {
+        println!("Invalid enemy catalog: {error}");
+        app_exit_event_writer.send(AppExit);
+        return;
+    }
+    if let Err(error) = achievement_catalog.validate() {
+        println!("Invalid achievement catalog: {error}");
+        app_exit_event_writer.send(AppExit);
+        return;
+    }
+
+    match GameAssets::from_manifest(&asset_server, asset_manifest, enemy_catalog) {
+        Ok(game_assets) => {
+            commands.insert_resource(game_assets);
+            commands.insert_resource(enemy_catalog.clone());
+            commands.insert_resource(achievement_catalog.clone());
+        }
+        Err(error) => {
+            println!("Invalid asset manifest: {error}");
+            app_exit_event_writer.send(AppExit);
+        }
+    }
+}
+
+/// Enter `AppState::MainMenu` once every handle is loaded and every level is valid,
+/// quit on the first missing file
+pub fn check_game_assets(
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    levels: Res<Assets<Level>>,
+    enemy_catalog: Res<EnemyCatalog>,
+    mut loading_progress: ResMut<LoadingProgress>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+) {
+    let handles = game_assets.untyped_handles();
+    let mut loaded = 0;
+
+    for handle in handles.iter() {
+        match asset_server.get_load_state(handle.id()) {
+            Some(LoadState::Loaded) => loaded += 1,
+            Some(LoadState::Failed) => {
+                let path = asset_server
+                    .get_path(handle.id())
+                    .map_or("unknown path".to_string(), |path| path.to_string());
+                println!("Failed to load asset `{path}`, check the assets folder");
+                app_exit_event_writer.send(AppExit);
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    loading_progress.loaded = loaded;
+    loading_progress.total = handles.len();
+
+    if loaded < handles.len() {
+        return;
+    }
+
+    for level in game_assets
+        .levels
+        .iter()
+        .filter_map(|handle| levels.get(handle))
+    {
+        if let Err(error) = level.validate(&enemy_catalog) {
+            println!("Invalid level: {error}");
+            app_exit_event_writer.send(AppExit);
+            return;
+        }
+        // the manifest is only known here, the editor cannot change the music
+        if let Some(music) = level.music.as_ref() {
+            if !game_assets.music.contains_key(music) {
+                println!(
+                    "Invalid level: level `{}` plays music `{music}` missing from the manifest",
+                    level.name
+                );
+                app_exit_event_writer.send(AppExit);
+                return;
+            }
+        }
+    }
+    next_app_state.set(AppState::MainMenu);
+}
+
+pub fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            LoadingScreen {},
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Loading...",
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ),
+                LoadingText {},
+            ));
+        });
+}
+
+pub fn update_loading_screen(
+    loading_progress: Res<LoadingProgress>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+) {
+    if loading_progress.is_changed() {
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = format!(
+                "Loading... {}/{}",
+                loading_progress.loaded, loading_progress.total
+            );
+        }
+    }
+}
+
+pub fn despawn_loading_screen(
+    mut commands: Commands,
+    loading_screen_query: Query<Entity, With<LoadingScreen>>,
+) {
+    for loading_screen_entity in loading_screen_query.iter() {
+        commands.entity(loading_screen_entity).despawn_recursive();
+    }
+}
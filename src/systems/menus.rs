@@ -0,0 +1,382 @@
+use crate::achievements::AchievementCatalog;
+use crate::components::{AchievementsScreen, GameOverScreen, MainMenu, MainMenuText, StatsScreen};
+use crate::game_modes::GameMode;
+use crate::input::Action;
+use crate::levels::Level;
+use crate::resources::{
+    AchievementProgress, EditorMode, GameAssets, HighScores, LevelProgress, LevelSelection,
+    LocalPlayers, Score, Stats,
+};
+use crate::states::AppState;
+use bevy::prelude::*;
+
+/// Leaderboard entries listed on the game over screen
+const GAME_OVER_HIGH_SCORES: usize = 5;
+
+fn screen_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn screen_text(value: impl Into<String>) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size: 40.0,
+            color: Color::WHITE,
+            ..Default::default()
+        },
+    )
+    .with_text_alignment(TextAlignment::Center)
+}
+
+// region:			--- Main menu
+
+/// Game mode and every level name, the selected one between arrows
+fn main_menu_text(
+    game_assets: &GameAssets,
+    levels: &Assets<Level>,
+    selected: usize,
+    game_mode: GameMode,
+    editor_mode: &EditorMode,
+) -> String {
+    let mut text = format!("BEVY XP\n\nMode: < {} >\n", game_mode.name());
+    for (index, handle) in game_assets.levels.iter().enumerate() {
+        let name = levels.get(handle).map_or("?", |level| level.name.as_str());
+        if index == selected {
+            text.push_str(&format!("\n> {name} <"));
+        } else {
+            text.push_str(&format!("\n{name}"));
+        }
+    }
+    if editor_mode.enabled {
+        text.push_str("\n\nUp/Down: level\nEnter: edit");
+    } else {
+        text.push_str("\n\nUp/Down: level\nLeft/Right: mode\nEnter: play");
+    }
+    text.push_str("\nSpace: achievements and stats");
+    text
+}
+
+pub fn spawn_main_menu(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    levels: Res<Assets<Level>>,
+    level_selection: Res<LevelSelection>,
+    game_mode: Res<GameMode>,
+    editor_mode: Res<EditorMode>,
+) {
+    let text = main_menu_text(
+        &game_assets,
+        &levels,
+        level_selection.index,
+        *game_mode,
+        &editor_mode,
+    );
+
+    commands
+        .spawn((screen_node(), MainMenu {}))
+        .with_children(|parent| {
+            parent.spawn((screen_text(text), MainMenuText {}));
+        });
+}
+
+pub fn update_main_menu_text(
+    game_assets: Res<GameAssets>,
+    levels: Res<Assets<Level>>,
+    level_selection: Res<LevelSelection>,
+    game_mode: Res<GameMode>,
+    editor_mode: Res<EditorMode>,
+    mut text_query: Query<&mut Text, With<MainMenuText>>,
+) {
+    if level_selection.is_changed() || game_mode.is_changed() {
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = main_menu_text(
+                &game_assets,
+                &levels,
+                level_selection.index,
+                *game_mode,
+                &editor_mode,
+            );
+        }
+    }
+}
+
+pub fn despawn_main_menu(mut commands: Commands, main_menu_query: Query<Entity, With<MainMenu>>) {
+    for main_menu_entity in main_menu_query.iter() {
+        commands.entity(main_menu_entity).despawn_recursive();
+    }
+}
+
+/// Up and down pick a level, left and right a game mode, Fire lists the achievements,
+/// Confirm copies the level into the `Level` resource and plays it, or edits it in editor mode
+#[allow(clippy::too_many_arguments)]
+pub fn main_menu_input(
+    mut commands: Commands,
+    action_input: Res<Input<Action>>,
+    game_assets: Res<GameAssets>,
+    levels: Res<Assets<Level>>,
+    editor_mode: Res<EditorMode>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::MoveLeft) {
+        *game_mode = game_mode.previous();
+    }
+    if action_input.just_pressed(Action::MoveRight) {
+        *game_mode = game_mode.next();
+    }
+
+    let level_count = game_assets.levels.len();
+    if action_input.just_pressed(Action::MoveUp) {
+        level_selection.index = (level_selection.index + level_count - 1) % level_count;
+    }
+    if action_input.just_pressed(Action::MoveDown) {
+        level_selection.index = (level_selection.index + 1) % level_count;
+    }
+
+    if action_input.just_pressed(Action::Fire) {
+        next_app_state.set(AppState::Achievements);
+        return;
+    }
+
+    if action_input.just_pressed(Action::Confirm) {
+        let handle = &game_assets.levels[level_selection.index];
+        if let Some(level) = levels.get(handle) {
+            commands.insert_resource(level.clone());
+            if editor_mode.enabled {
+                next_app_state.set(AppState::Editor);
+            } else {
+                next_app_state.set(AppState::Playing);
+            }
+        }
+    }
+}
+
+// endregion:		--- Main menu
+
+// region:			--- Game over
+
+/// Run outcome, scores, run summary and the leaderboard of the game mode
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_game_over_screen(
+    mut commands: Commands,
+    score: Res<Score>,
+    local_players: Res<LocalPlayers>,
+    level_progress: Res<LevelProgress>,
+    game_mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    stats: Res<Stats>,
+) {
+    let title = if level_progress.completed {
+        "LEVEL COMPLETE"
+    } else if level_progress.time_up {
+        "TIME UP"
+    } else {
+        "GAME OVER"
+    };
+    let mut text = format!("{title}\n{}\n", game_mode.name());
+    for player_id in local_players.players.iter() {
+        text.push_str(&format!(
+            "\n{}: {}",
+            player_id.name(),
+            score.get(*player_id)
+        ));
+    }
+
+    text.push_str(&format!(
+        "\n\n{} stars, {:.0} px, {:.1}s",
+        stats.run.stars_collected, stats.run.distance, stats.run.time_survived
+    ));
+
+    let best = high_scores.get(*game_mode);
+    if !best.is_empty() {
+        text.push_str("\n\nBest runs");
+    }
+    for (rank, high_score) in best.iter().take(GAME_OVER_HIGH_SCORES).enumerate() {
+        text.push_str(&format!(
+            "\n{}. {} {} ({:.1}s)",
+            rank + 1,
+            high_score.name,
+            high_score.score,
+            high_score.seconds
+        ));
+    }
+    text.push_str("\n\nEnter: main menu");
+
+    commands
+        .spawn((screen_node(), GameOverScreen {}))
+        .with_children(|parent| {
+            parent.spawn(screen_text(text));
+        });
+}
+
+pub fn despawn_game_over_screen(
+    mut commands: Commands,
+    game_over_screen_query: Query<Entity, With<GameOverScreen>>,
+) {
+    for game_over_screen_entity in game_over_screen_query.iter() {
+        commands.entity(game_over_screen_entity).despawn_recursive();
+    }
+}
+
+pub fn game_over_input(
+    action_input: Res<Input<Action>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Confirm) {
+        next_app_state.set(AppState::MainMenu);
+    }
+}
+
+// endregion:		--- Game over
+
+// region:			--- Achievements
+
+/// Unlocked achievements first marked, lifetime goals with their progress
+pub fn spawn_achievements_screen(
+    mut commands: Commands,
+    achievement_catalog: Res<AchievementCatalog>,
+    achievement_progress: Res<AchievementProgress>,
+) {
+    let unlocked = achievement_catalog
+        .achievements
+        .iter()
+        .filter(|achievement| achievement_progress.unlocked.contains(&achievement.id))
+        .count();
+    let mut text = format!(
+        "ACHIEVEMENTS\n{unlocked}/{}\n",
+        achievement_catalog.achievements.len()
+    );
+
+    for achievement in achievement_catalog.achievements.iter() {
+        if achievement_progress.unlocked.contains(&achievement.id) {
+            text.push_str(&format!(
+                "\n[x] {}: {}",
+                achievement.name, achievement.description
+            ));
+        } else if achievement.goal.is_lifetime() {
+            let (value, target) = achievement.goal.progress(&achievement_progress);
+            text.push_str(&format!(
+                "\n[ ] {}: {} ({value}/{target})",
+                achievement.name, achievement.description
+            ));
+        } else {
+            text.push_str(&format!(
+                "\n[ ] {}: {}",
+                achievement.name, achievement.description
+            ));
+        }
+    }
+    text.push_str("\n\nSpace: stats\nEnter: main menu");
+
+    commands
+        .spawn((screen_node(), AchievementsScreen {}))
+        .with_children(|parent| {
+            // smaller than the other screens to fit the descriptions
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+pub fn despawn_achievements_screen(
+    mut commands: Commands,
+    achievements_screen_query: Query<Entity, With<AchievementsScreen>>,
+) {
+    for achievements_screen_entity in achievements_screen_query.iter() {
+        commands
+            .entity(achievements_screen_entity)
+            .despawn_recursive();
+    }
+}
+
+/// Fire pages to the stats
+pub fn achievements_screen_input(
+    action_input: Res<Input<Action>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Fire) {
+        next_app_state.set(AppState::Stats);
+    } else if action_input.just_pressed(Action::Confirm) || action_input.just_pressed(Action::Pause)
+    {
+        next_app_state.set(AppState::MainMenu);
+    }
+}
+
+// endregion:		--- Achievements
+
+// region:			--- Stats
+
+/// Lifetime totals, deaths split by cause
+pub fn spawn_stats_screen(mut commands: Commands, stats: Res<Stats>) {
+    let lifetime = &stats.lifetime;
+    let mut text = format!(
+        "STATS\n\nRuns: {}\nStars collected: {}\nDistance travelled: {:.0} px\n\
+         Time survived: {:.0}s\nLongest run: {:.1}s\nEnemies spawned: {}\nNear misses: {}\n\
+         Deaths: {}",
+        stats.runs,
+        lifetime.stars_collected,
+        lifetime.distance,
+        lifetime.time_survived,
+        stats.longest_run,
+        lifetime.enemies_spawned,
+        lifetime.near_misses,
+        lifetime.total_deaths()
+    );
+    for (cause, count) in lifetime.deaths.iter() {
+        text.push_str(&format!("\n  by {}: {count}", cause.name()));
+    }
+    text.push_str("\n\nSpace: achievements\nEnter: main menu");
+
+    commands
+        .spawn((screen_node(), StatsScreen {}))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+pub fn despawn_stats_screen(
+    mut commands: Commands,
+    stats_screen_query: Query<Entity, With<StatsScreen>>,
+) {
+    for stats_screen_entity in stats_screen_query.iter() {
+        commands.entity(stats_screen_entity).despawn_recursive();
+    }
+}
+
+/// Fire pages back to the achievements
+pub fn stats_screen_input(
+    action_input: Res<Input<Action>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Fire) {
+        next_app_state.set(AppState::Achievements);
+    } else if action_input.just_pressed(Action::Confirm) || action_input.just_pressed(Action::Pause)
+    {
+        next_app_state.set(AppState::MainMenu);
+    }
+}
+
+// endregion:		--- Stats
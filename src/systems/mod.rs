@@ -1,22 +1,79 @@
+pub mod achievements;
+pub mod audio;
+pub mod bosses;
+pub mod camera;
 pub mod collisions;
+pub mod editor;
+pub mod game_modes;
+pub mod input;
+pub mod levels;
+pub mod lives;
+pub mod loading;
+pub mod menus;
 pub mod movements;
+pub mod near_misses;
+pub mod obstacles;
+pub mod pause;
+pub mod population;
+pub mod power_ups;
 pub mod scores;
 pub mod spawns;
+pub mod stats;
 
+use crate::components::Obstacle;
 use crate::events::GameOver;
-use bevy::{app::AppExit, prelude::*, window::PrimaryWindow};
+use crate::states::AppState;
+use bevy::prelude::*;
+use rand::random;
 
-pub fn exit_game(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut app_exit_event_writer: EventWriter<AppExit>,
-) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        app_exit_event_writer.send(AppExit);
-    }
-}
+/// Attempts at finding a free spot before giving up on a spawn
+const FREE_POSITION_ATTEMPTS: usize = 32;
 
-pub fn handle_game_over(mut game_over_event_reader: EventReader<GameOver>) {
+pub fn handle_game_over(
+    mut game_over_event_reader: EventReader<GameOver>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
     for event in game_over_event_reader.read() {
         println!("Your final score is: {}", event.score.to_string());
+        next_app_state.set(AppState::GameOver);
     }
 }
+
+/// Closest of `candidates` to `position`, players are the usual candidates
+pub(crate) fn closest_position(
+    position: Vec2,
+    candidates: impl Iterator<Item = Vec2>,
+) -> Option<Vec2> {
+    candidates.min_by(|a, b| {
+        a.distance_squared(position)
+            .total_cmp(&b.distance_squared(position))
+    })
+}
+
+/// World position of the mouse cursor, `None` while it is out of the window
+pub(crate) fn cursor_world_position(
+    window: &Window,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+/// Random spot of the arena at least `clearance` away from every obstacle
+pub(crate) fn random_free_position(
+    arena: Vec2,
+    obstacle_query: &Query<(&Transform, &Obstacle)>,
+    clearance: f32,
+) -> Option<Vec2> {
+    (0..FREE_POSITION_ATTEMPTS)
+        .map(|_| Vec2::new(random::<f32>() * arena.x, random::<f32>() * arena.y))
+        .find(|position| {
+            obstacle_query.iter().all(|(transform, obstacle)| {
+                obstacle
+                    .shape
+                    .contact(transform.translation.truncate(), *position, clearance)
+                    .is_none()
+            })
+        })
+}
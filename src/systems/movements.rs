@@ -1,49 +1,42 @@
-use crate::components::{Enemy, Player};
-use crate::{ENEMY_SIZE, ENEMY_SPEED, PLAYER_SIZE, PLAYER_SPEED};
-use bevy::{prelude::*, window::PrimaryWindow};
-use rand::{Rng, RngCore};
+use crate::audio::Sfx;
+use crate::components::{
+    ActivePowerUps, Enemy, EnemyProjectile, MovementAxis, Player, PlayerProjectile,
+};
+use crate::events::{EnemyBounced, PlaySfx};
+use crate::levels::Level;
+use crate::power_ups::PowerUpKind;
+use crate::resources::EnemyTimeScale;
+use crate::steering::{Kinematic, Steering, SteeringContext};
+use crate::{PLAYER_SIZE, PLAYER_SPEED, PROJECTILE_SIZE, SPEED_BOOST_FACTOR};
+use bevy::prelude::*;
 
 pub fn player_movement(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut player_query: Query<&mut Transform, With<Player>>,
+    mut player_query: Query<(&mut Transform, &MovementAxis, &ActivePowerUps), With<Player>>,
     time: Res<Time>,
 ) {
-    if let Ok(mut transform) = player_query.get_single_mut() {
-        let mut direction = Vec3::ZERO;
-
-        if keyboard_input.pressed(KeyCode::Left) || keyboard_input.pressed(KeyCode::Q) {
-            direction += Vec3::new(-1.0, 0.0, 0.0);
-        }
-        if keyboard_input.pressed(KeyCode::Right) || keyboard_input.pressed(KeyCode::S) {
-            direction += Vec3::new(1.0, 0.0, 0.0);
-        }
-        if keyboard_input.pressed(KeyCode::Up) || keyboard_input.pressed(KeyCode::A) {
-            direction += Vec3::new(0.0, 1.0, 0.0);
-        }
-        if keyboard_input.pressed(KeyCode::Down) || keyboard_input.pressed(KeyCode::W) {
-            direction += Vec3::new(0.0, -1.0, 0.0);
-        }
-
-        if direction.length() > 0.0 {
-            direction = direction.normalize();
-        }
-
-        transform.translation += direction * PLAYER_SPEED * time.delta_seconds();
+    for (mut transform, movement_axis, active_power_ups) in player_query.iter_mut() {
+        let speed = if active_power_ups.is_active(PowerUpKind::SpeedBoost) {
+            PLAYER_SPEED * SPEED_BOOST_FACTOR
+        } else {
+            PLAYER_SPEED
+        };
+        let direction = movement_axis.value.extend(0.0);
+        transform.translation += direction * speed * time.delta_seconds();
     }
 }
 
 pub fn confine_player_movement(
     mut player_query: Query<&mut Transform, With<Player>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    level: Res<Level>,
 ) {
-    if let Ok(mut player_transform) = player_query.get_single_mut() {
-        let window = window_query.get_single().unwrap();
+    let arena = level.size();
 
+    for mut player_transform in player_query.iter_mut() {
         let half_player_size = PLAYER_SIZE / 2.0;
         let x_min = 0.0 + half_player_size;
-        let x_max = window.width() - half_player_size;
+        let x_max = arena.x - half_player_size;
         let y_min = 0.0 + half_player_size;
-        let y_max = window.height() - half_player_size;
+        let y_max = arena.y - half_player_size;
 
         let mut translation = player_transform.translation;
 
@@ -65,53 +58,161 @@ pub fn confine_player_movement(
     }
 }
 
-pub fn enemy_movement(mut enemy_query: Query<(&mut Transform, &Enemy)>, time: Res<Time>) {
+pub fn enemy_movement(
+    mut enemy_query: Query<(&mut Transform, &Enemy)>,
+    enemy_time_scale: Res<EnemyTimeScale>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds() * enemy_time_scale.value;
+
     for (mut transform, enemy) in enemy_query.iter_mut() {
         let direction = Vec3::new(enemy.direction.x, enemy.direction.y, 0.0);
-        transform.translation += direction * ENEMY_SPEED * time.delta_seconds();
+        transform.translation += direction * enemy.speed * delta;
+    }
+}
+
+/// What steered entities see: the players, the neighbors of their kind and the arena
+fn steering_context(
+    player_query: &Query<(&Transform, &MovementAxis), With<Player>>,
+    arena: Vec2,
+    neighbors: Vec<Vec2>,
+) -> SteeringContext {
+    SteeringContext {
+        players: player_query
+            .iter()
+            .map(|(transform, movement_axis)| Kinematic {
+                position: transform.translation.truncate(),
+                velocity: movement_axis.value * PLAYER_SPEED,
+            })
+            .collect(),
+        neighbors,
+        bounds: Rect::new(0.0, 0.0, arena.x, arena.y),
+    }
+}
+
+/// Steered enemies keep moving through `enemy_movement`, their direction and speed follow the steering
+pub fn steer_enemies(
+    mut enemy_query: Query<(&Transform, &mut Enemy, Option<&mut Steering>)>,
+    player_query: Query<(&Transform, &MovementAxis), With<Player>>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    let arena = level.size();
+    let neighbors = enemy_query
+        .iter()
+        .map(|(transform, _, _)| transform.translation.truncate())
+        .collect();
+    let context = steering_context(&player_query, arena, neighbors);
+
+    for (transform, mut enemy, steering) in enemy_query.iter_mut() {
+        let Some(mut steering) = steering else {
+            continue;
+        };
+        let agent = Kinematic {
+            position: transform.translation.truncate(),
+            velocity: enemy.direction * enemy.speed,
+        };
+        let velocity = steering.steer(agent, &context, time.delta_seconds());
+
+        // keep the last heading while stopped so wall bounces still apply
+        if let Some(direction) = velocity.try_normalize() {
+            enemy.direction = direction;
+        }
+        enemy.speed = velocity.length();
     }
 }
 
 pub fn update_enemy_direction(
-    mut commands: Commands,
-    mut enemy_query: Query<(&mut Transform, &mut Enemy)>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut enemy_bounced_event_writer: EventWriter<EnemyBounced>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Enemy)>,
+    level: Res<Level>,
 ) {
-    let window = window_query.get_single().unwrap();
+    let arena = level.size();
 
-    let half_enemy_size = ENEMY_SIZE / 2.0;
-    let x_min = 0.0 + half_enemy_size;
-    let x_max = window.width() - half_enemy_size;
-    let y_min = 0.0 + half_enemy_size;
-    let y_max = window.height() - half_enemy_size;
+    for (enemy_entity, transform, mut enemy) in enemy_query.iter_mut() {
+        let half_enemy_size = enemy.size / 2.0;
+        let x_min = 0.0 + half_enemy_size;
+        let x_max = arena.x - half_enemy_size;
+        let y_min = 0.0 + half_enemy_size;
+        let y_max = arena.y - half_enemy_size;
 
-    for (transform, mut enemy) in enemy_query.iter_mut() {
         let mut direction_changed = false;
 
+        // only flip when heading out, an enemy spawned past an edge walks back in
         let translation = transform.translation;
-        if translation.x < x_min || translation.x > x_max {
+        if (translation.x < x_min && enemy.direction.x < 0.0)
+            || (translation.x > x_max && enemy.direction.x > 0.0)
+        {
             enemy.direction.x *= -1.0;
             direction_changed = true;
         }
-        if translation.y < y_min || translation.y > y_max {
+        if (translation.y < y_min && enemy.direction.y < 0.0)
+            || (translation.y > y_max && enemy.direction.y > 0.0)
+        {
             enemy.direction.y *= -1.0;
             direction_changed = true;
         }
 
         // play soundFX
         if direction_changed {
-            if rand::thread_rng().gen_bool(0.5) {
-                commands.spawn(AudioBundle {
-                    source: asset_server.load("audio/pluck_001.ogg"),
-                    ..Default::default()
-                });
-            } else {
-                commands.spawn(AudioBundle {
-                    source: asset_server.load("audio/pluck_002.ogg"),
-                    ..Default::default()
-                });
-            }
+            play_sfx_event_writer.send(PlaySfx {
+                sfx: Sfx::Bounce,
+                position: Some(translation.truncate()),
+            });
+            enemy_bounced_event_writer.send(EnemyBounced {
+                enemy: enemy_entity,
+                position: translation.truncate(),
+            });
+        }
+    }
+}
+
+/// Move enemy projectiles, despawning the ones leaving the arena
+pub fn projectile_movement(
+    mut commands: Commands,
+    mut projectile_query: Query<(Entity, &mut Transform, &EnemyProjectile)>,
+    level: Res<Level>,
+    enemy_time_scale: Res<EnemyTimeScale>,
+    time: Res<Time>,
+) {
+    let arena = level.size();
+    let delta = time.delta_seconds() * enemy_time_scale.value;
+
+    for (projectile_entity, mut transform, projectile) in projectile_query.iter_mut() {
+        transform.translation += projectile.velocity.extend(0.0) * delta;
+
+        let translation = transform.translation;
+        if translation.x < -PROJECTILE_SIZE
+            || translation.x > arena.x + PROJECTILE_SIZE
+            || translation.y < -PROJECTILE_SIZE
+            || translation.y > arena.y + PROJECTILE_SIZE
+        {
+            commands.entity(projectile_entity).despawn();
+        }
+    }
+}
+
+/// Move player projectiles, despawning them when their lifetime ends or they leave the arena
+pub fn player_projectile_movement(
+    mut commands: Commands,
+    mut projectile_query: Query<(Entity, &mut Transform, &mut PlayerProjectile)>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    let arena = level.size();
+
+    for (projectile_entity, mut transform, mut projectile) in projectile_query.iter_mut() {
+        transform.translation += projectile.velocity.extend(0.0) * time.delta_seconds();
+
+        let translation = transform.translation;
+        if projectile.lifetime.tick(time.delta()).finished()
+            || translation.x < -PROJECTILE_SIZE
+            || translation.x > arena.x + PROJECTILE_SIZE
+            || translation.y < -PROJECTILE_SIZE
+            || translation.y > arena.y + PROJECTILE_SIZE
+        {
+            commands.entity(projectile_entity).despawn();
         }
     }
 }
@@ -0,0 +1,76 @@
+use crate::audio::Sfx;
+use crate::components::NearMissPopup;
+use crate::events::{NearMiss, PlaySfx, ScoreGained, ScoreReason};
+use crate::{NEAR_MISS_POINTS, NEAR_MISS_POPUP_TIME, PLAYER_SIZE};
+use bevy::prelude::*;
+
+/// Pixels a popup rises over its lifetime
+const POPUP_RISE: f32 = 40.0;
+
+/// Bonus points, a sound and a popup over the player for every near miss
+pub fn reward_near_misses(
+    mut commands: Commands,
+    mut near_miss_event_reader: EventReader<NearMiss>,
+    mut score_gained_event_writer: EventWriter<ScoreGained>,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+) {
+    for event in near_miss_event_reader.read() {
+        score_gained_event_writer.send(ScoreGained {
+            player_id: event.player_id,
+            amount: NEAR_MISS_POINTS,
+            reason: ScoreReason::NearMiss,
+        });
+        play_sfx_event_writer.send(PlaySfx {
+            sfx: Sfx::NearMiss,
+            position: Some(event.position),
+        });
+
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("close! +{NEAR_MISS_POINTS}"),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::CYAN,
+                        ..Default::default()
+                    },
+                ),
+                transform: Transform::from_translation(
+                    (event.position + Vec2::Y * PLAYER_SIZE / 2.0).extend(1.0),
+                ),
+                ..Default::default()
+            },
+            NearMissPopup {
+                timer: Timer::from_seconds(NEAR_MISS_POPUP_TIME, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+pub fn animate_near_miss_popups(
+    mut commands: Commands,
+    mut popup_query: Query<(Entity, &mut Transform, &mut Text, &mut NearMissPopup)>,
+    time: Res<Time>,
+) {
+    for (popup_entity, mut transform, mut text, mut popup) in popup_query.iter_mut() {
+        if popup.timer.tick(time.delta()).finished() {
+            commands.entity(popup_entity).despawn();
+            continue;
+        }
+
+        transform.translation.y += POPUP_RISE / NEAR_MISS_POPUP_TIME * time.delta_seconds();
+        let alpha = 1.0 - popup.timer.percent();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+pub fn despawn_near_miss_popups(
+    mut commands: Commands,
+    popup_query: Query<Entity, With<NearMissPopup>>,
+) {
+    for popup_entity in popup_query.iter() {
+        commands.entity(popup_entity).despawn();
+    }
+}
@@ -0,0 +1,133 @@
+use crate::audio::Sfx;
+use crate::components::{Enemy, EnemyProjectile, Obstacle, Player, PlayerProjectile};
+use crate::events::{EnemyBounced, PlaySfx};
+use crate::obstacles::ObstacleShape;
+use crate::{PLAYER_SIZE, PROJECTILE_SIZE};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+/// Projectiles of both sides
+type ProjectileFilter = Or<(With<EnemyProjectile>, With<PlayerProjectile>)>;
+
+const OBSTACLE_COLOR: Color = Color::rgb(0.35, 0.35, 0.45);
+
+/// Spawn an obstacle with a flat colored visual matching its shape
+pub fn spawn_obstacle(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec2,
+    shape: ObstacleShape,
+) {
+    let transform = Transform::from_translation(position.extend(-1.0));
+
+    match shape {
+        ObstacleShape::Rect { .. } => {
+            commands.spawn((
+                SpriteBundle {
+                    transform,
+                    sprite: Sprite {
+                        color: OBSTACLE_COLOR,
+                        custom_size: Some(shape.size()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Obstacle { shape },
+            ));
+        }
+        ObstacleShape::Circle { radius } => {
+            commands.spawn((
+                MaterialMesh2dBundle {
+                    transform,
+                    mesh: meshes.add(shape::Circle::new(radius).into()).into(),
+                    material: materials.add(ColorMaterial::from(OBSTACLE_COLOR)),
+                    ..Default::default()
+                },
+                Obstacle { shape },
+            ));
+        }
+    }
+}
+
+pub fn despawn_obstacles(mut commands: Commands, obstacle_query: Query<Entity, With<Obstacle>>) {
+    for obstacle_entity in obstacle_query.iter() {
+        commands.entity(obstacle_entity).despawn();
+    }
+}
+
+/// Reflect enemies heading into an obstacle about its surface normal
+pub fn bounce_enemies_off_obstacles(
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut enemy_bounced_event_writer: EventWriter<EnemyBounced>,
+    mut enemy_query: Query<(Entity, &mut Transform, &mut Enemy), Without<Obstacle>>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+) {
+    for (enemy_entity, mut transform, mut enemy) in enemy_query.iter_mut() {
+        for (obstacle_transform, obstacle) in obstacle_query.iter() {
+            let Some(contact) = obstacle.shape.contact(
+                obstacle_transform.translation.truncate(),
+                transform.translation.truncate(),
+                enemy.size / 2.0,
+            ) else {
+                continue;
+            };
+
+            transform.translation += (contact.normal * contact.depth).extend(0.0);
+            let approach = enemy.direction.dot(contact.normal);
+            if approach < 0.0 {
+                enemy.direction -= 2.0 * approach * contact.normal;
+                play_sfx_event_writer.send(PlaySfx {
+                    sfx: Sfx::Bounce,
+                    position: Some(transform.translation.truncate()),
+                });
+                enemy_bounced_event_writer.send(EnemyBounced {
+                    enemy: enemy_entity,
+                    position: transform.translation.truncate(),
+                });
+            }
+        }
+    }
+}
+
+/// Push players out of obstacles, the part of the move along the surface is kept,
+/// before the arena confinement so an obstacle by a wall cannot push them out
+pub fn slide_players_along_obstacles(
+    mut player_query: Query<&mut Transform, (With<Player>, Without<Obstacle>)>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+) {
+    for mut transform in player_query.iter_mut() {
+        for (obstacle_transform, obstacle) in obstacle_query.iter() {
+            if let Some(contact) = obstacle.shape.contact(
+                obstacle_transform.translation.truncate(),
+                transform.translation.truncate(),
+                PLAYER_SIZE / 2.0,
+            ) {
+                transform.translation += (contact.normal * contact.depth).extend(0.0);
+            }
+        }
+    }
+}
+
+/// Projectiles of both sides stop on obstacles
+pub fn obstacles_block_projectiles(
+    mut commands: Commands,
+    projectile_query: Query<(Entity, &Transform), ProjectileFilter>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+) {
+    for (projectile_entity, transform) in projectile_query.iter() {
+        let blocked = obstacle_query.iter().any(|(obstacle_transform, obstacle)| {
+            obstacle
+                .shape
+                .contact(
+                    obstacle_transform.translation.truncate(),
+                    transform.translation.truncate(),
+                    PROJECTILE_SIZE / 2.0,
+                )
+                .is_some()
+        });
+
+        if blocked {
+            commands.entity(projectile_entity).despawn();
+        }
+    }
+}
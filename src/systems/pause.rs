@@ -0,0 +1,178 @@
+use crate::components::{PauseMenu, PauseMenuText};
+use crate::input::Action;
+use crate::resources::{CameraSettings, InputBindings, QuitConfirmation};
+use crate::states::SimulationState;
+use bevy::{app::AppExit, prelude::*, window::WindowFocused};
+
+fn confirm_quit_text(input_bindings: &InputBindings) -> String {
+    format!(
+        "Quit the game?\n\n{}: confirm\n{}: cancel",
+        input_bindings.key_hint(Action::Confirm),
+        input_bindings.key_hint(Action::Pause)
+    )
+}
+
+fn paused_text(camera_settings: &CameraSettings, input_bindings: &InputBindings) -> String {
+    let screen_shake = if camera_settings.screen_shake {
+        "on"
+    } else {
+        "off"
+    };
+    format!(
+        "PAUSED\n\n{}: resume\n{}: screen shake {screen_shake}\n{}: quit",
+        input_bindings.key_hint(Action::Pause),
+        input_bindings.key_hint(Action::Toggle),
+        input_bindings.key_hint(Action::Confirm)
+    )
+}
+
+// region:			--- Simulation
+
+pub fn toggle_simulation(
+    action_input: Res<Input<Action>>,
+    mut next_simulation_state: ResMut<NextState<SimulationState>>,
+) {
+    if action_input.just_pressed(Action::Pause) {
+        next_simulation_state.set(SimulationState::Paused);
+    }
+}
+
+pub fn pause_on_focus_lost(
+    mut window_focused_event_reader: EventReader<WindowFocused>,
+    simulation_state: Res<State<SimulationState>>,
+    mut next_simulation_state: ResMut<NextState<SimulationState>>,
+) {
+    for event in window_focused_event_reader.read() {
+        if !event.focused && *simulation_state.get() == SimulationState::Running {
+            next_simulation_state.set(SimulationState::Paused);
+        }
+    }
+}
+
+/// Freeze the virtual clock (spawn timers and movements) and every playing sound
+pub fn pause_simulation(
+    mut time: ResMut<Time<Virtual>>,
+    audio_query: Query<&AudioSink>,
+    spatial_audio_query: Query<&SpatialAudioSink>,
+) {
+    time.pause();
+    for sink in audio_query.iter() {
+        sink.pause();
+    }
+    for sink in spatial_audio_query.iter() {
+        sink.pause();
+    }
+}
+
+pub fn resume_simulation(
+    mut time: ResMut<Time<Virtual>>,
+    audio_query: Query<&AudioSink>,
+    spatial_audio_query: Query<&SpatialAudioSink>,
+    mut quit_confirmation: ResMut<QuitConfirmation>,
+) {
+    time.unpause();
+    for sink in audio_query.iter() {
+        sink.play();
+    }
+    for sink in spatial_audio_query.iter() {
+        sink.play();
+    }
+    quit_confirmation.pending = false;
+}
+
+// endregion:		--- Simulation
+
+// region:			--- Pause menu
+
+pub fn spawn_pause_menu(
+    mut commands: Commands,
+    camera_settings: Res<CameraSettings>,
+    input_bindings: Res<InputBindings>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..Default::default()
+            },
+            PauseMenu {},
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    paused_text(&camera_settings, &input_bindings),
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+                PauseMenuText {},
+            ));
+        });
+}
+
+pub fn despawn_pause_menu(
+    mut commands: Commands,
+    pause_menu_query: Query<Entity, With<PauseMenu>>,
+) {
+    for pause_menu_entity in pause_menu_query.iter() {
+        commands.entity(pause_menu_entity).despawn_recursive();
+    }
+}
+
+/// Pause resumes (or cancels the quit prompt), Toggle flips the screen shake,
+/// Confirm asks then confirms quitting
+pub fn pause_menu_input(
+    action_input: Res<Input<Action>>,
+    mut quit_confirmation: ResMut<QuitConfirmation>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut next_simulation_state: ResMut<NextState<SimulationState>>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+) {
+    if action_input.just_pressed(Action::Pause) {
+        if quit_confirmation.pending {
+            quit_confirmation.pending = false;
+        } else {
+            next_simulation_state.set(SimulationState::Running);
+        }
+    }
+    if action_input.just_pressed(Action::Toggle) && !quit_confirmation.pending {
+        camera_settings.screen_shake = !camera_settings.screen_shake;
+    }
+    if action_input.just_pressed(Action::Confirm) {
+        if quit_confirmation.pending {
+            app_exit_event_writer.send(AppExit);
+        } else {
+            quit_confirmation.pending = true;
+        }
+    }
+}
+
+pub fn update_pause_menu_text(
+    quit_confirmation: Res<QuitConfirmation>,
+    camera_settings: Res<CameraSettings>,
+    input_bindings: Res<InputBindings>,
+    mut text_query: Query<&mut Text, With<PauseMenuText>>,
+) {
+    if quit_confirmation.is_changed() || camera_settings.is_changed() || input_bindings.is_changed()
+    {
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = if quit_confirmation.pending {
+                confirm_quit_text(&input_bindings)
+            } else {
+                paused_text(&camera_settings, &input_bindings)
+            };
+        }
+    }
+}
+
+// endregion:		--- Pause menu
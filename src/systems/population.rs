@@ -0,0 +1,136 @@
+use crate::components::{Boss, Enemy, EnemyProjectile, Player, PowerUp, SpawnedAt, Star};
+use crate::levels::Level;
+use crate::population::{EntityKind, SpawnPolicy};
+use crate::resources::{EntityBudget, PopulationCaps};
+use bevy::{ecs::query::ReadOnlyWorldQuery, prelude::*};
+
+/// Capped entities: stars, enemies but bosses, power-ups and projectiles
+type CappedFilter = (
+    Or<(
+        With<Star>,
+        (With<Enemy>, Without<Boss>),
+        With<PowerUp>,
+        With<EnemyProjectile>,
+    )>,
+    Without<SpawnedAt>,
+);
+
+type CappedQuery<'w, 's, F> = Query<'w, 's, (Entity, &'static Transform, &'static SpawnedAt), F>;
+
+/// Stamp the spawn time of new capped entities, the oldest go first under `DespawnOldest`
+pub fn stamp_spawn_time(
+    mut commands: Commands,
+    new_entity_query: Query<Entity, CappedFilter>,
+    time: Res<Time>,
+) {
+    for entity in new_entity_query.iter() {
+        commands.entity(entity).insert(SpawnedAt {
+            seconds: time.elapsed_seconds(),
+        });
+    }
+}
+
+pub fn update_entity_budget(
+    star_query: Query<(), With<Star>>,
+    enemy_query: Query<(), (With<Enemy>, Without<Boss>)>,
+    power_up_query: Query<(), With<PowerUp>>,
+    projectile_query: Query<(), With<EnemyProjectile>>,
+    mut entity_budget: ResMut<EntityBudget>,
+) {
+    entity_budget.counts = EntityKind::ALL
+        .into_iter()
+        .map(|kind| {
+            let count = match kind {
+                EntityKind::Star => star_query.iter().len(),
+                EntityKind::Enemy => enemy_query.iter().len(),
+                EntityKind::PowerUp => power_up_query.iter().len(),
+                EntityKind::Projectile => projectile_query.iter().len(),
+            };
+            (kind, count)
+        })
+        .collect();
+}
+
+fn capped_entities<F: ReadOnlyWorldQuery>(query: &CappedQuery<F>) -> Vec<(Entity, Vec2, f32)> {
+    query
+        .iter()
+        .map(|(entity, transform, spawned_at)| {
+            (entity, transform.translation.truncate(), spawned_at.seconds)
+        })
+        .collect()
+}
+
+/// Entities to remove to bring `entities` back to `max` under `policy`
+fn over_cap(
+    mut entities: Vec<(Entity, Vec2, f32)>,
+    max: usize,
+    policy: SpawnPolicy,
+    players: &[Vec2],
+    fallback: Vec2,
+) -> Vec<Entity> {
+    if entities.len() <= max {
+        return Vec::new();
+    }
+
+    match policy {
+        SpawnPolicy::Skip => return Vec::new(),
+        // oldest last, ties broken by entity for a stable order
+        SpawnPolicy::DespawnOldest => entities.sort_by(|a, b| {
+            b.2.total_cmp(&a.2)
+                .then_with(|| b.0.index().cmp(&a.0.index()))
+        }),
+        // farthest last, measured to the closest player
+        SpawnPolicy::DespawnFarthest => {
+            let clearance = |position: Vec2| {
+                players
+                    .iter()
+                    .map(|player| player.distance_squared(position))
+                    .reduce(f32::min)
+                    .unwrap_or(fallback.distance_squared(position))
+            };
+            entities.sort_by(|a, b| clearance(a.1).total_cmp(&clearance(b.1)));
+        }
+    }
+
+    entities
+        .split_off(max)
+        .into_iter()
+        .map(|(entity, _, _)| entity)
+        .collect()
+}
+
+/// Despawn the entities over the cap of kinds with a despawn policy
+#[allow(clippy::too_many_arguments)]
+pub fn enforce_population_caps(
+    mut commands: Commands,
+    star_query: CappedQuery<With<Star>>,
+    enemy_query: CappedQuery<(With<Enemy>, Without<Boss>)>,
+    power_up_query: CappedQuery<With<PowerUp>>,
+    projectile_query: CappedQuery<With<EnemyProjectile>>,
+    player_query: Query<&Transform, With<Player>>,
+    level: Res<Level>,
+    population_caps: Res<PopulationCaps>,
+) {
+    let arena = level.size();
+    let arena_center = arena / 2.0;
+    let players = player_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect::<Vec<_>>();
+
+    for kind in EntityKind::ALL {
+        let Some(cap) = population_caps.get(kind) else {
+            continue;
+        };
+        let entities = match kind {
+            EntityKind::Star => capped_entities(&star_query),
+            EntityKind::Enemy => capped_entities(&enemy_query),
+            EntityKind::PowerUp => capped_entities(&power_up_query),
+            EntityKind::Projectile => capped_entities(&projectile_query),
+        };
+
+        for entity in over_cap(entities, cap.max, cap.policy, &players, arena_center) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use super::random_free_position;
+use crate::audio::Sfx;
+use crate::components::{
+    ActivePowerUps, Boss, Enemy, EnemyProjectile, Obstacle, Player, PowerUp, PowerUpHud, Star,
+};
+use crate::events::{CameraShake, PlaySfx};
+use crate::game_modes::GameMode;
+use crate::levels::Level;
+use crate::population::EntityKind;
+use crate::power_ups::PowerUpKind;
+use crate::resources::{
+    EnemyTimeScale, EntityBudget, GameAssets, PopulationCaps, PowerUpSpawnTimer,
+};
+use crate::{
+    BOMB_RADIUS, CAMERA_EXPLOSION_TRAUMA, MAGNET_RADIUS, MAGNET_SPEED, PLAYER_SIZE, POWER_UP_SIZE,
+    SLOW_TIME_FACTOR,
+};
+use bevy::prelude::*;
+
+/// Enemies and their projectiles, bosses survive bombs
+type BombTargetFilter = (Or<(With<Enemy>, With<EnemyProjectile>)>, Without<Boss>);
+
+// region:			--- Spawns
+
+pub fn tick_power_up_spawn_timer(
+    mut power_up_spawn_timer: ResMut<PowerUpSpawnTimer>,
+    time: Res<Time>,
+) {
+    power_up_spawn_timer.timer.tick(time.delta());
+}
+
+pub fn spawn_power_ups_over_time(
+    mut commands: Commands,
+    level: Res<Level>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+    game_assets: Res<GameAssets>,
+    power_up_spawn_timer: Res<PowerUpSpawnTimer>,
+    population_caps: Res<PopulationCaps>,
+    entity_budget: Res<EntityBudget>,
+) {
+    if power_up_spawn_timer.timer.finished()
+        && population_caps.allows_spawn(EntityKind::PowerUp, &entity_budget)
+    {
+        let arena = level.size();
+        let Some(position) = random_free_position(arena, &obstacle_query, POWER_UP_SIZE / 2.0)
+        else {
+            return;
+        };
+        let kind = PowerUpKind::random();
+
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(position.extend(0.0)),
+                texture: game_assets.power_up_sprite.clone(),
+                sprite: Sprite {
+                    color: kind.color(),
+                    custom_size: Some(Vec2::splat(POWER_UP_SIZE)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            PowerUp { kind },
+        ));
+    }
+}
+
+pub fn despawn_power_ups(mut commands: Commands, power_up_query: Query<Entity, With<PowerUp>>) {
+    for power_up_entity in power_up_query.iter() {
+        commands.entity(power_up_entity).despawn();
+    }
+}
+
+// endregion:		--- Spawns
+
+// region:			--- Effects
+
+/// Timed power-ups go on the player, bombs go off right away
+pub fn player_hit_power_up(
+    mut commands: Commands,
+    mut play_sfx_event_writer: EventWriter<PlaySfx>,
+    mut camera_shake_event_writer: EventWriter<CameraShake>,
+    mut player_query: Query<(&Player, &Transform, &mut ActivePowerUps)>,
+    power_up_query: Query<(Entity, &Transform, &PowerUp)>,
+    bomb_target_query: Query<(Entity, &Transform), BombTargetFilter>,
+) {
+    for (power_up_entity, power_up_transform, power_up) in power_up_query.iter() {
+        // a power-up goes to the first player touching it
+        let collector = player_query.iter_mut().find(|(_, player_transform, _)| {
+            let distance = player_transform
+                .translation
+                .distance(power_up_transform.translation);
+            distance < PLAYER_SIZE / 2.0 + POWER_UP_SIZE / 2.0
+        });
+        let Some((player, player_transform, mut active_power_ups)) = collector else {
+            continue;
+        };
+
+        println!("{} picked up {}!", player.id.name(), power_up.kind.name());
+        match power_up.kind.duration() {
+            Some(duration) => active_power_ups.activate(power_up.kind, duration),
+            None => {
+                for (target_entity, target_transform) in bomb_target_query.iter() {
+                    let distance = player_transform
+                        .translation
+                        .distance(target_transform.translation);
+                    if distance < BOMB_RADIUS {
+                        commands.entity(target_entity).despawn();
+                    }
+                }
+                play_sfx_event_writer.send(PlaySfx {
+                    sfx: Sfx::Explosion,
+                    position: Some(player_transform.translation.truncate()),
+                });
+                camera_shake_event_writer.send(CameraShake {
+                    trauma: CAMERA_EXPLOSION_TRAUMA,
+                });
+            }
+        }
+        play_sfx_event_writer.send(PlaySfx {
+            sfx: Sfx::PowerUp,
+            position: Some(power_up_transform.translation.truncate()),
+        });
+        commands.entity(power_up_entity).despawn();
+    }
+}
+
+pub fn tick_power_ups(mut player_query: Query<(&Player, &mut ActivePowerUps)>, time: Res<Time>) {
+    for (player, mut active_power_ups) in player_query.iter_mut() {
+        active_power_ups.timers.retain(|kind, timer| {
+            let running = !timer.tick(time.delta()).finished();
+            if !running {
+                println!("{} of {} wore off", kind.name(), player.id.name());
+            }
+            running
+        });
+    }
+}
+
+/// Stars around a magnet player drift toward it
+pub fn attract_stars(
+    mut star_query: Query<&mut Transform, (With<Star>, Without<Player>)>,
+    player_query: Query<(&Transform, &ActivePowerUps), With<Player>>,
+    time: Res<Time>,
+) {
+    let magnets = player_query
+        .iter()
+        .filter(|(_, active_power_ups)| active_power_ups.is_active(PowerUpKind::Magnet))
+        .map(|(transform, _)| transform.translation.truncate())
+        .collect::<Vec<_>>();
+    if magnets.is_empty() {
+        return;
+    }
+
+    for mut star_transform in star_query.iter_mut() {
+        let position = star_transform.translation.truncate();
+        let Some(magnet) = magnets
+            .iter()
+            .filter(|magnet| magnet.distance(position) < MAGNET_RADIUS)
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+        else {
+            continue;
+        };
+
+        let step = (*magnet - position).normalize_or_zero() * MAGNET_SPEED * time.delta_seconds();
+        star_transform.translation += step.extend(0.0);
+    }
+}
+
+/// Enemy speed of the game mode, slowed down while any player runs slow-time
+pub fn update_enemy_time_scale(
+    player_query: Query<&ActivePowerUps, With<Player>>,
+    game_mode: Res<GameMode>,
+    mut enemy_time_scale: ResMut<EnemyTimeScale>,
+) {
+    let slowed = player_query
+        .iter()
+        .any(|active_power_ups| active_power_ups.is_active(PowerUpKind::SlowTime));
+    let slow_factor = if slowed { SLOW_TIME_FACTOR } else { 1.0 };
+    let value = game_mode.enemy_speed_factor() * slow_factor;
+
+    if enemy_time_scale.value != value {
+        enemy_time_scale.value = value;
+    }
+}
+
+pub fn reset_enemy_time_scale(mut enemy_time_scale: ResMut<EnemyTimeScale>) {
+    enemy_time_scale.value = 1.0;
+}
+
+// endregion:		--- Effects
+
+// region:			--- HUD
+
+pub fn spawn_power_up_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..Default::default()
+        }),
+        PowerUpHud {},
+    ));
+}
+
+pub fn update_power_up_hud(
+    player_query: Query<(&Player, &ActivePowerUps)>,
+    mut hud_query: Query<&mut Text, With<PowerUpHud>>,
+) {
+    let mut lines = Vec::new();
+    for (player, active_power_ups) in player_query.iter() {
+        for (kind, timer) in active_power_ups.timers.iter() {
+            lines.push(format!(
+                "{} - {} {:.0}s",
+                player.id.name(),
+                kind.name(),
+                timer.remaining_secs().ceil()
+            ));
+        }
+    }
+    let value = lines.join("\n");
+
+    for mut text in hud_query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn despawn_power_up_hud(mut commands: Commands, hud_query: Query<Entity, With<PowerUpHud>>) {
+    for hud_entity in hud_query.iter() {
+        commands.entity(hud_entity).despawn_recursive();
+    }
+}
+
+// endregion:		--- HUD
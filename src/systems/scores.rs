@@ -1,19 +1,90 @@
-use crate::events::GameOver;
-use crate::resources::{HighScores, Score};
+use crate::components::Combo;
+use crate::events::{GameOver, ScoreGained, ScoreReason};
+use crate::game_modes::GameMode;
+use crate::resources::{HighScore, HighScores, LevelProgress, Score};
 use bevy::{prelude::*, window::PrimaryWindow};
 
+pub fn reset_score(mut score: ResMut<Score>) {
+    score.values.clear();
+}
+
+/// Single writer of `Score`, every gain goes through `ScoreGained`
+pub fn apply_score_gains(
+    mut score_gained_event_reader: EventReader<ScoreGained>,
+    mut score: ResMut<Score>,
+) {
+    for event in score_gained_event_reader.read() {
+        score.add(event.player_id, event.amount);
+        match &event.reason {
+            ScoreReason::Star { kind, multiplier } if *multiplier > 1 => println!(
+                "{} +{} ({:?} star x{multiplier})",
+                event.player_id.name(),
+                event.amount,
+                kind
+            ),
+            ScoreReason::Star { kind, .. } => println!(
+                "{} +{} ({:?} star)",
+                event.player_id.name(),
+                event.amount,
+                kind
+            ),
+            ScoreReason::EnemyDestroyed => println!(
+                "{} +{} (enemy destroyed)",
+                event.player_id.name(),
+                event.amount
+            ),
+            ScoreReason::NearMiss => {
+                println!("{} +{} (near miss)", event.player_id.name(), event.amount)
+            }
+            ScoreReason::BossDefeated { name } => println!(
+                "{} +{} ({name} defeated)",
+                event.player_id.name(),
+                event.amount
+            ),
+        }
+    }
+}
+
+/// Lose the streak of players who stopped collecting stars
+pub fn tick_combos(mut combo_query: Query<&mut Combo>, time: Res<Time>) {
+    for mut combo in combo_query.iter_mut() {
+        if combo.streak > 0 && combo.timer.tick(time.delta()).just_finished() {
+            combo.streak = 0;
+        }
+    }
+}
+
 pub fn update_score(score: Res<Score>) {
     if score.is_changed() {
-        println!("Score: {}", score.value.to_string())
+        for (player_id, value) in score.values.iter() {
+            println!("{} score: {}", player_id.name(), value);
+        }
     }
 }
 
+/// Rank every player in the leaderboard of the game mode with the run length,
+/// runs ranked by time only count once finished
 pub fn update_high_scores(
     mut game_over_event_reader: EventReader<GameOver>,
+    game_mode: Res<GameMode>,
+    level_progress: Res<LevelProgress>,
     mut high_scores: ResMut<HighScores>,
 ) {
     for event in game_over_event_reader.read() {
-        high_scores.scores.push(("Player".to_string(), event.score));
+        // completing the level before the star target is not a timed finish
+        if game_mode.ranks_by_time() && !level_progress.target_reached {
+            continue;
+        }
+        for (player_id, score) in event.player_scores.iter() {
+            high_scores.add(
+                *game_mode,
+                HighScore {
+                    name: player_id.name().to_string(),
+                    score: *score,
+                    seconds: event.summary.time_survived,
+                },
+            );
+        }
     }
 }
 
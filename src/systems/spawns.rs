@@ -1,62 +1,150 @@
-use crate::components::{Enemy, Player, Star};
-use crate::resources::{EnemySpawnTimer, StarSpawnTimer};
-use crate::{NUMBER_OF_ENEMIES, NUMBER_OF_STARS};
-use bevy::{prelude::*, window::PrimaryWindow};
-use rand::random;
+use super::{closest_position, random_free_position};
+use crate::components::{
+    ActivePowerUps, Aim, Combo, Enemy, EnemyProjectile, Health, Lives, MovementAxis,
+    NearMissTracker, Obstacle, Player, PlayerProjectile, Sniper, Splitter, Star, StarKind, Weapon,
+};
+use crate::enemies::{EnemyBehavior, EnemyCatalog};
+use crate::events::EnemyBounced;
+use crate::game_modes::GameMode;
+use crate::levels::Level;
+use crate::population::EntityKind;
+use crate::resources::{
+    EnemySpawnTimer, EnemyTimeScale, EntityBudget, GameAssets, LocalPlayers, PopulationCaps,
+    PowerUpSpawnTimer, ShooterMode, StarSpawnTimer,
+};
+use crate::steering::Steering;
+use crate::{
+    PLAYER_FIRE_INTERVAL, PLAYER_HEALTH, PLAYER_PROJECTILE_LIFETIME, PLAYER_PROJECTILE_SPEED,
+    PLAYER_SIZE, PROJECTILE_SIZE, STAR_FADE_TIME, STAR_LIFETIME, STAR_SIZE,
+};
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
 
 // region:			--- Player
 
 pub fn spawn_player(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    game_assets: Res<GameAssets>,
+    local_players: Res<LocalPlayers>,
+    shooter_mode: Res<ShooterMode>,
+    game_mode: Res<GameMode>,
 ) {
-    let window = window_query.get_single().unwrap();
+    let arena = level.size();
+    let player_count = local_players.players.len() as f32;
 
-    commands.spawn((
-        SpriteBundle {
-            transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
-            texture: asset_server.load("sprites/ball_blue_large.png"),
-            ..Default::default()
-        },
-        Player {},
-    ));
-}
+    for (index, player_id) in local_players.players.iter().enumerate() {
+        // players side by side around the arena center
+        let x_offset = (index as f32 - (player_count - 1.0) / 2.0) * PLAYER_SIZE * 2.0;
 
-pub fn spawn_camera(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
-    let window = window_query.get_single().unwrap();
+        let mut player = commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(arena.x / 2.0 + x_offset, arena.y / 2.0, 0.0),
+                texture: game_assets.player_sprite.clone(),
+                sprite: Sprite {
+                    color: player_id.color(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Player { id: *player_id },
+            Health::new(PLAYER_HEALTH),
+            Lives {
+                remaining: game_mode.lives(),
+            },
+            MovementAxis::default(),
+            ActivePowerUps::default(),
+            Combo::default(),
+            NearMissTracker::default(),
+            Aim::default(),
+        ));
 
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
-        ..Default::default()
-    });
+        if shooter_mode.enabled {
+            player.insert(Weapon {
+                cooldown: Timer::from_seconds(PLAYER_FIRE_INTERVAL, TimerMode::Once),
+            });
+        }
+    }
+}
+
+pub fn despawn_players(mut commands: Commands, player_query: Query<Entity, With<Player>>) {
+    for player_entity in player_query.iter() {
+        commands.entity(player_entity).despawn();
+    }
 }
 
 // endregion:		--- Player
 
 // region:			--- Enemies
 
-pub fn spawn_enemies(
-    mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+/// Spawn an enemy of the catalog type `name` with its behavior components
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    enemy_catalog: &EnemyCatalog,
+    name: &str,
+    position: Vec2,
+    direction: Vec2,
 ) {
-    let window = window_query.get_single().unwrap();
+    let Some(enemy_type) = enemy_catalog.get(name) else {
+        println!("Unknown enemy type `{name}`");
+        return;
+    };
+    let [r, g, b] = enemy_type.tint;
 
-    for _ in 0..NUMBER_OF_ENEMIES {
-        let x_random = random::<f32>() * window.width();
-        let y_random = random::<f32>() * window.height();
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(x_random, y_random, 0.0),
-                texture: asset_server.load("sprites/ball_red_large.png"),
+    let mut enemy = commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(position.extend(0.0)),
+            texture: game_assets.enemy_sprite(name),
+            sprite: Sprite {
+                color: Color::rgb(r, g, b),
+                custom_size: Some(Vec2::splat(enemy_type.size)),
                 ..Default::default()
             },
-            Enemy {
-                direction: Vec2::new(x_random, y_random).normalize(),
-            },
-        ));
+            ..Default::default()
+        },
+        Enemy {
+            direction,
+            speed: enemy_type.speed,
+            size: enemy_type.size,
+            health: enemy_type.health,
+            points: enemy_type.points,
+        },
+    ));
+
+    match &enemy_type.behavior {
+        EnemyBehavior::Bouncer => {}
+        EnemyBehavior::Steered {
+            max_acceleration,
+            behaviors,
+        } => {
+            enemy.insert(Steering::new(
+                enemy_type.speed,
+                *max_acceleration,
+                behaviors.clone(),
+            ));
+        }
+        EnemyBehavior::Splitter { into, count } => {
+            enemy.insert(Splitter {
+                into: into.clone(),
+                count: *count,
+            });
+        }
+        EnemyBehavior::Sniper {
+            fire_interval,
+            projectile_speed,
+        } => {
+            enemy.insert(Sniper {
+                fire_timer: Timer::from_seconds(*fire_interval, TimerMode::Repeating),
+                projectile_speed: *projectile_speed,
+            });
+        }
+    }
+}
+
+pub fn despawn_enemies(mut commands: Commands, enemy_query: Query<Entity, With<Enemy>>) {
+    for enemy_entity in enemy_query.iter() {
+        commands.entity(enemy_entity).despawn();
     }
 }
 
@@ -64,79 +152,275 @@ pub fn tick_enemy_spawn_timer(mut enemy_spawn_timer: ResMut<EnemySpawnTimer>, ti
     enemy_spawn_timer.timer.tick(time.delta());
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_enemies_over_time(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    level: Res<Level>,
+    game_mode: Res<GameMode>,
     enemy_spawn_timer: Res<EnemySpawnTimer>,
+    population_caps: Res<PopulationCaps>,
+    entity_budget: Res<EntityBudget>,
 ) {
-    if enemy_spawn_timer.timer.finished() {
-        let window = window_query.get_single().unwrap();
-        let x_random = random::<f32>() * window.width();
-        let y_random = random::<f32>() * window.height();
+    if level.random_enemies
+        && game_mode.has_enemies()
+        && enemy_spawn_timer.timer.finished()
+        && population_caps.allows_spawn(EntityKind::Enemy, &entity_budget)
+    {
+        let Some(name) = enemy_catalog.pick_random(&level.enemy_types) else {
+            return;
+        };
+        let clearance = enemy_catalog
+            .get(name)
+            .map_or(0.0, |enemy_type| enemy_type.size / 2.0);
+        if let Some(position) = random_free_position(level.size(), &obstacle_query, clearance) {
+            spawn_enemy(
+                &mut commands,
+                &game_assets,
+                &enemy_catalog,
+                name,
+                position,
+                position.normalize(),
+            );
+        }
+    }
+}
+
+/// Replace splitters hitting a wall by their children, fanned out around the new heading,
+/// children over the enemy cap are left out
+pub fn split_enemies(
+    mut commands: Commands,
+    mut enemy_bounced_event_reader: EventReader<EnemyBounced>,
+    splitter_query: Query<(&Enemy, &Splitter)>,
+    game_assets: Res<GameAssets>,
+    enemy_catalog: Res<EnemyCatalog>,
+    population_caps: Res<PopulationCaps>,
+    entity_budget: Res<EntityBudget>,
+) {
+    let mut free_slots = population_caps.free_slots(EntityKind::Enemy, &entity_budget);
+
+    for event in enemy_bounced_event_reader.read() {
+        let Ok((enemy, splitter)) = splitter_query.get(event.enemy) else {
+            continue;
+        };
+        // the splitter leaves its slot to a child
+        free_slots = free_slots.saturating_add(1);
+        let count = splitter
+            .count
+            .min(free_slots.try_into().unwrap_or(u32::MAX));
+        free_slots -= count as usize;
+
+        for index in 0..count {
+            let spread = if splitter.count > 1 {
+                index as f32 / (splitter.count - 1) as f32 - 0.5
+            } else {
+                0.0
+            };
+            let direction = Vec2::from_angle(spread * FRAC_PI_2).rotate(enemy.direction);
+            spawn_enemy(
+                &mut commands,
+                &game_assets,
+                &enemy_catalog,
+                &splitter.into,
+                event.position,
+                direction,
+            );
+        }
+        commands.entity(event.enemy).despawn();
+    }
+}
+
+// endregion:		--- Enemies
+
+// region:			--- Projectiles
+
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    position: Vec2,
+    velocity: Vec2,
+) {
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(position.extend(0.0)),
+            texture: game_assets.projectile_sprite.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(PROJECTILE_SIZE)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        EnemyProjectile { velocity },
+    ));
+}
+
+/// Snipers shoot at the closest player
+pub fn fire_sniper_projectiles(
+    mut commands: Commands,
+    mut sniper_query: Query<(&Transform, &mut Sniper)>,
+    player_query: Query<&Transform, With<Player>>,
+    game_assets: Res<GameAssets>,
+    enemy_time_scale: Res<EnemyTimeScale>,
+    time: Res<Time>,
+) {
+    // slow time slows the fire rate too
+    let delta = time.delta().mul_f32(enemy_time_scale.value);
+    for (sniper_transform, mut sniper) in sniper_query.iter_mut() {
+        if !sniper.fire_timer.tick(delta).just_finished() {
+            continue;
+        }
+        let position = sniper_transform.translation.truncate();
+        let Some(target) = closest_position(
+            position,
+            player_query
+                .iter()
+                .map(|transform| transform.translation.truncate()),
+        ) else {
+            continue;
+        };
+
+        spawn_projectile(
+            &mut commands,
+            &game_assets,
+            position,
+            (target - position).normalize_or_zero() * sniper.projectile_speed,
+        );
+    }
+}
+
+/// Armed players shoot along their aim, at most once per cooldown
+pub fn fire_player_projectiles(
+    mut commands: Commands,
+    mut player_query: Query<(&Player, &Transform, &Aim, &mut Weapon)>,
+    game_assets: Res<GameAssets>,
+    time: Res<Time>,
+) {
+    for (player, transform, aim, mut weapon) in player_query.iter_mut() {
+        if !weapon.cooldown.tick(time.delta()).finished() || !aim.firing {
+            continue;
+        }
+        weapon.cooldown.reset();
 
         commands.spawn((
             SpriteBundle {
-                transform: Transform::from_xyz(x_random, y_random, 0.0),
-                texture: asset_server.load("sprites/ball_red_large.png"),
+                transform: Transform::from_translation(transform.translation),
+                texture: game_assets.projectile_sprite.clone(),
+                sprite: Sprite {
+                    color: player.id.color(),
+                    custom_size: Some(Vec2::splat(PROJECTILE_SIZE)),
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            Enemy {
-                direction: Vec2::new(x_random, y_random).normalize(),
+            PlayerProjectile {
+                owner: player.id,
+                velocity: aim.direction * PLAYER_PROJECTILE_SPEED,
+                lifetime: Timer::from_seconds(PLAYER_PROJECTILE_LIFETIME, TimerMode::Once),
             },
         ));
     }
 }
 
-// endregion:		--- Enemies
-
-// region:			--- Stars
+pub fn despawn_player_projectiles(
+    mut commands: Commands,
+    projectile_query: Query<Entity, With<PlayerProjectile>>,
+) {
+    for projectile_entity in projectile_query.iter() {
+        commands.entity(projectile_entity).despawn();
+    }
+}
 
-pub fn spawn_stars(
+pub fn despawn_projectiles(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+    projectile_query: Query<Entity, With<EnemyProjectile>>,
 ) {
-    let window = window_query.get_single().unwrap();
+    for projectile_entity in projectile_query.iter() {
+        commands.entity(projectile_entity).despawn();
+    }
+}
 
-    for _ in 0..NUMBER_OF_STARS {
-        let x_random = random::<f32>() * window.width();
-        let y_random = random::<f32>() * window.height();
+// endregion:		--- Projectiles
 
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(x_random, y_random, 0.0),
-                texture: asset_server.load("sprites/star.png"),
+// region:			--- Stars
+
+/// Spawn a star of a random kind
+pub fn spawn_star(commands: &mut Commands, game_assets: &GameAssets, position: Vec2) {
+    let kind = StarKind::random();
+
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(position.extend(0.0)),
+            texture: game_assets.star_sprite.clone(),
+            sprite: Sprite {
+                color: kind.color(),
                 ..Default::default()
             },
-            Star {},
-        ));
+            ..Default::default()
+        },
+        Star {
+            kind,
+            lifetime: Timer::from_seconds(STAR_LIFETIME, TimerMode::Once),
+        },
+    ));
+}
+
+pub fn despawn_stars(mut commands: Commands, star_query: Query<Entity, With<Star>>) {
+    for star_entity in star_query.iter() {
+        commands.entity(star_entity).despawn();
+    }
+}
+
+/// Fade stars out over the end of their lifetime, then remove them
+pub fn expire_stars(
+    mut commands: Commands,
+    mut star_query: Query<(Entity, &mut Star, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (star_entity, mut star, mut sprite) in star_query.iter_mut() {
+        if star.lifetime.tick(time.delta()).finished() {
+            commands.entity(star_entity).despawn();
+            continue;
+        }
+
+        let remaining = star.lifetime.remaining_secs();
+        if remaining < STAR_FADE_TIME {
+            sprite.color.set_a(remaining / STAR_FADE_TIME);
+        }
     }
 }
 
+pub fn reset_spawn_timers(
+    mut star_spawn_timer: ResMut<StarSpawnTimer>,
+    mut enemy_spawn_timer: ResMut<EnemySpawnTimer>,
+    mut power_up_spawn_timer: ResMut<PowerUpSpawnTimer>,
+) {
+    star_spawn_timer.timer.reset();
+    enemy_spawn_timer.timer.reset();
+    power_up_spawn_timer.timer.reset();
+}
+
 pub fn tick_star_spawn_timer(mut star_spawn_timer: ResMut<StarSpawnTimer>, time: Res<Time>) {
     star_spawn_timer.timer.tick(time.delta());
 }
 
 pub fn spawn_stars_over_time(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+    game_assets: Res<GameAssets>,
     star_spawn_timer: Res<StarSpawnTimer>,
+    population_caps: Res<PopulationCaps>,
+    entity_budget: Res<EntityBudget>,
 ) {
-    if star_spawn_timer.timer.finished() {
-        let window = window_query.get_single().unwrap();
-        let x_random = random::<f32>() * window.width();
-        let y_random = random::<f32>() * window.height();
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(x_random, y_random, 0.0),
-                texture: asset_server.load("sprites/star.png"),
-                ..Default::default()
-            },
-            Star {},
-        ));
+    if star_spawn_timer.timer.finished()
+        && population_caps.allows_spawn(EntityKind::Star, &entity_budget)
+    {
+        let arena = level.size();
+        if let Some(position) = random_free_position(arena, &obstacle_query, STAR_SIZE / 2.0) {
+            spawn_star(&mut commands, &game_assets, position);
+        }
     }
 }
 
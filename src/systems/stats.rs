@@ -0,0 +1,70 @@
+use crate::components::{Enemy, Player};
+use crate::events::{NearMiss, ScoreGained, ScoreReason};
+use crate::resources::{LevelProgress, Stats};
+use crate::settings::Persistent;
+use crate::{PLAYER_SIZE, STATS_PATH};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+pub fn load_stats(mut commands: Commands) {
+    let stats = match Stats::load(STATS_PATH) {
+        Ok(stats) => stats,
+        Err(error) => {
+            println!("No player statistics ({error})");
+            Stats::default()
+        }
+    };
+
+    commands.insert_resource(stats);
+}
+
+pub fn reset_run_stats(mut stats: ResMut<Stats>) {
+    stats.run = Default::default();
+}
+
+/// Accumulate the metrics of the run, before any system that can send `GameOver`
+/// so its summary is up to date
+pub fn track_stats(
+    mut score_gained_event_reader: EventReader<ScoreGained>,
+    mut near_miss_event_reader: EventReader<NearMiss>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    spawned_enemy_query: Query<(), Added<Enemy>>,
+    level_progress: Res<LevelProgress>,
+    mut stats: ResMut<Stats>,
+    mut last_positions: Local<HashMap<Entity, Vec2>>,
+) {
+    let stars = score_gained_event_reader
+        .read()
+        .filter(|event| matches!(event.reason, ScoreReason::Star { .. }))
+        .count();
+    stats.run.stars_collected += stars as u32;
+    stats.run.near_misses += near_miss_event_reader.read().count() as u32;
+    stats.run.enemies_spawned += spawned_enemy_query.iter().count() as u32;
+    stats.run.time_survived = level_progress.elapsed;
+
+    let mut positions = HashMap::new();
+    for (player_entity, transform) in player_query.iter() {
+        let position = transform.translation.truncate();
+        if let Some(last_position) = last_positions.get(&player_entity) {
+            // respawns teleport the player, they are no travel
+            let step = last_position.distance(position);
+            if step < PLAYER_SIZE {
+                stats.run.distance += step;
+            }
+        }
+        positions.insert(player_entity, position);
+    }
+    *last_positions = positions;
+}
+
+/// Add the run to the lifetime metrics and save them, quitting through the pause menu counts too
+pub fn end_run_stats(mut stats: ResMut<Stats>) {
+    stats.end_run();
+    println!(
+        "Run: {} stars, {:.0} px, {:.1}s",
+        stats.run.stars_collected, stats.run.distance, stats.run.time_survived
+    );
+    if let Err(error) = stats.save(STATS_PATH) {
+        println!("Failed to save player statistics: {error}");
+    }
+}